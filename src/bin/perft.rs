@@ -0,0 +1,134 @@
+//! Run perft from the command line to validate the move generation outside of the tests
+//!
+//! usage: perft [options] <depth> [fen]          divide of the position at depth 1 to 64, the start position by default
+//!        perft [options] --epd <file> [depth]   check the counts of an EPD file, up to depth if given or 64.
+//!                                               Print the first depth differing for each position
+//! options: --bulk        count the moves at depth 1 without playing them
+//!          --hash <mb>   cache the counts of the subtrees in a table of mb megabytes, up to 65536,
+//!                        shared out between the threads checking an EPD file
+use std::env;
+use std::fs;
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::engine::board::Board;
+use chess::engine::perft::{divide, check_positions, PerftMode, PerftPosition, MAX_HASH_SIZE};
+use chess::engine::search::MAX_DEPTH;
+
+const USAGE: &str = "usage: perft [--bulk] [--hash <mb>] <depth> [fen]\n       perft [--bulk] [--hash <mb>] --epd <file> [depth]";
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mode = match parse_mode(&mut args) {
+        Some(mode) => mode,
+        None => {
+            usage();
+            process::exit(1);
+        }
+    };
+    chess::init(); // generate the tables out of the timings
+    let success = match args.first().map(String::as_str) {
+        Some("--epd") => match (args.get(1), args.get(2).map(|depth| depth.parse())) {
+            (Some(file), None) => run_epd(file, MAX_DEPTH, &mode),
+            (Some(file), Some(Ok(depth))) if depth <= MAX_DEPTH => run_epd(file, depth, &mode),
+            _ => usage(),
+        }
+        Some(depth) => match depth.parse() {
+            Ok(depth) if (1..=MAX_DEPTH).contains(&depth) => {
+                let board = match args.get(1) {
+                    Some(_) => Board::try_from_fen(&args[1..].join(" ")).and_then(|board| board.validate().map(|_| board)),
+                    None => Ok(Board::new_board()),
+                };
+                match board {
+                    Ok(board) => {
+                        run_divide(&board, depth, &mode);
+                        true
+                    }
+                    Err(err) => {
+                        eprintln!("invalid fen: {}", err);
+                        false
+                    }
+                }
+            }
+            _ => usage(),
+        }
+        None => usage(),
+    };
+    if !success{
+        process::exit(1);
+    }
+}
+
+/// Remove the leading options from the arguments, None if one is invalid
+fn parse_mode(args: &mut Vec<String>) -> Option<PerftMode>{
+    let mut mode = PerftMode::default();
+    loop {
+        match args.first().map(String::as_str) {
+            Some("--bulk") => {
+                mode.bulk = true;
+                args.remove(0);
+            }
+            Some("--hash") => {
+                mode.hash_size = args.get(1)?.parse().ok().filter(|&size| size <= MAX_HASH_SIZE)?;
+                args.drain(..2);
+            }
+            _ => return Some(mode),
+        }
+    }
+}
+
+fn usage() -> bool{
+    eprintln!("{}", USAGE);
+    false
+}
+
+/// Nodes per second, the time is rounded up to one millisecond
+fn nps(nodes: u64, time: Duration) -> u64{
+    nodes * 1000 / (time.as_millis() as u64).max(1)
+}
+
+/// Print the nodes under each move of the position
+fn run_divide(board: &Board, depth: u32, mode: &PerftMode){
+    let start = Instant::now();
+    let divide = divide(board, depth, mode);
+    let time = start.elapsed();
+    for (mv, nodes) in divide.iter(){
+        println!("{}: {}", mv.to_uci(), nodes);
+    }
+    let nodes = divide.iter().map(|(_, nodes)| nodes).sum();
+    println!();
+    println!("Nodes searched: {}", nodes);
+    println!("Time: {} ms, {} nodes/s", time.as_millis(), nps(nodes, time));
+}
+
+/// Check every count of the EPD file up to max_depth on all the cores, return false if one differs
+fn run_epd(file: &str, max_depth: u32, mode: &PerftMode) -> bool{
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(err) => {
+            eprintln!("can not read {}: {}", file, err);
+            return false
+        }
+    };
+    let mut positions = Vec::new();
+    for (number, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()){
+        match line.parse::<PerftPosition>() {
+            Ok(position) => positions.push(position),
+            Err(err) => {
+                eprintln!("line {}: {}", number + 1, err);
+                return false
+            }
+        }
+    }
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    let start = Instant::now();
+    let (nodes, failures) = check_positions(&positions, max_depth, threads, mode);
+    let time = start.elapsed();
+    for failure in failures.iter(){
+        println!("FAIL {} depth {}: {} nodes, expected {}", failure.fen, failure.depth, failure.nodes, failure.expected);
+    }
+    println!("{} positions, {} nodes in {} ms, {} nodes/s, {} failure(s)",
+             positions.len(), nodes, time.as_millis(), nps(nodes, time), failures.len());
+    failures.is_empty()
+}
@@ -0,0 +1,299 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+use crate::engine::board::Case;
+use crate::engine::magic::magic_tables;
+use crate::engine::piece::Color;
+
+/// Set of cases, the bit n is set when the case n is in the set
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Bitboard(pub u64);
+impl Bitboard{
+    pub const EMPTY: Bitboard = Bitboard(0);
+
+    /// Set containing only case
+    pub const fn from_case(case: Case) -> Self{
+        Bitboard(1 << case.index())
+    }
+
+    pub fn contains(self, case: Case) -> bool{
+        self.0 & (1 << case.index()) != 0
+    }
+
+    pub fn is_empty(self) -> bool{
+        self.0 == 0
+    }
+
+    /// Number of cases in the set
+    pub fn count(self) -> u32{
+        self.0.count_ones()
+    }
+
+    /// Case with the lowest index in the set
+    pub fn first(self) -> Option<Case>{
+        match self.0 {
+            0 => None,
+            bits => Some(Case::new(bits.trailing_zeros() as usize)),
+        }
+    }
+}
+/// Iterate over the cases of the set, from the lowest index
+impl Iterator for Bitboard{
+    type Item = Case;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let case = self.first()?;
+        self.0 &= self.0 - 1;
+        Some(case)
+    }
+}
+/// Draw the set as a board, 8th rank on top
+impl fmt::Display for Bitboard{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for line in (0..8).rev(){
+            let cases: String = (0..8)
+                .map(|col| if self.contains(Case::new(col + 8 * line)) {'x'} else {'.'})
+                .collect();
+            writeln!(f, "{} {}", line + 1, cases)?;
+        }
+        write!(f, "  abcdefgh")
+    }
+}
+
+macro_rules! bit_op {
+    ($op:ident, $fn:ident, $op_assign:ident, $fn_assign:ident) => {
+        impl $op for Bitboard{
+            type Output = Bitboard;
+
+            fn $fn(self, rhs: Self) -> Self::Output {
+                Bitboard(self.0.$fn(rhs.0))
+            }
+        }
+        impl $op_assign for Bitboard{
+            fn $fn_assign(&mut self, rhs: Self) {
+                self.0.$fn_assign(rhs.0)
+            }
+        }
+    };
+}
+bit_op!(BitAnd, bitand, BitAndAssign, bitand_assign);
+bit_op!(BitOr, bitor, BitOrAssign, bitor_assign);
+bit_op!(BitXor, bitxor, BitXorAssign, bitxor_assign);
+impl Not for Bitboard{
+    type Output = Bitboard;
+
+    fn not(self) -> Self::Output {
+        Bitboard(!self.0)
+    }
+}
+
+/// Moves of the knight, as (line, column) offsets
+const KNIGHT_STEPS: [(isize, isize); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+/// Moves of the king, also the directions of the queen
+const KING_STEPS: [(isize, isize); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+pub(crate) const ROOK_DIRS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+pub(crate) const BISHOP_DIRS: [(isize, isize); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+
+static KNIGHT_ATTACKS: [Bitboard; 64] = leaper_table(&KNIGHT_STEPS);
+static KING_ATTACKS: [Bitboard; 64] = leaper_table(&KING_STEPS);
+/// Cases attacked by a pawn, indexed by color then case
+static PAWN_ATTACKS: [[Bitboard; 64]; 2] = [leaper_table(&[(1, -1), (1, 1)]), leaper_table(&[(-1, -1), (-1, 1)])];
+/// Cases strictly between two cases on the same line, column or diagonal
+static BETWEEN: [[Bitboard; 64]; 64] = line_tables().0;
+/// Whole line, column or diagonal going through two cases
+static LINE: [[Bitboard; 64]; 64] = line_tables().1;
+
+/// Index of the case at offset from case, if on the board
+const fn offset(case: usize, (line, col): (isize, isize)) -> Option<usize>{
+    let line = (case / 8) as isize + line;
+    let col = (case % 8) as isize + col;
+    if line >= 0 && line < 8 && col >= 0 && col < 8{
+        Some((line * 8 + col) as usize)
+    } else{
+        None
+    }
+}
+
+/// Cases reached from each case by one of the steps
+const fn leaper_table(steps: &[(isize, isize)]) -> [Bitboard; 64]{
+    let mut table = [Bitboard::EMPTY; 64];
+    let mut case = 0;
+    while case < 64{
+        let mut i = 0;
+        while i < steps.len(){
+            if let Some(target) = offset(case, steps[i]){
+                table[case].0 |= 1 << target;
+            }
+            i += 1;
+        }
+        case += 1;
+    }
+    table
+}
+
+/// Tables of the cases between two aligned cases and of the line through them,
+/// empty when the cases are not aligned
+const fn line_tables() -> ([[Bitboard; 64]; 64], [[Bitboard; 64]; 64]){
+    let mut between = [[Bitboard::EMPTY; 64]; 64];
+    let mut line = [[Bitboard::EMPTY; 64]; 64];
+    let mut from = 0;
+    while from < 64{
+        let mut dir = 0;
+        while dir < KING_STEPS.len(){
+            let (line_step, col_step) = KING_STEPS[dir];
+            // the whole line: from, its ray in the direction and its ray in the opposite direction
+            let full = (1 << from) | ray(from, (line_step, col_step)) | ray(from, (-line_step, -col_step));
+            let mut crossed = 0;
+            let mut current = from;
+            while let Some(to) = offset(current, (line_step, col_step)){
+                between[from][to].0 = crossed;
+                line[from][to].0 = full;
+                crossed |= 1 << to;
+                current = to;
+            }
+            dir += 1;
+        }
+        from += 1;
+    }
+    (between, line)
+}
+
+/// Cases reached from case in a direction on an empty board
+const fn ray(case: usize, dir: (isize, isize)) -> u64{
+    let mut ray = 0;
+    let mut current = case;
+    while let Some(target) = offset(current, dir){
+        ray |= 1 << target;
+        current = target;
+    }
+    ray
+}
+
+/// Cases reached from case in the directions, stopping on the first occupied case of each one.
+/// Slow, only used to generate the magic tables
+pub(crate) fn sliding_attacks(case: Case, occupied: Bitboard, dirs: &[(isize, isize)]) -> Bitboard{
+    let mut attacks = Bitboard::EMPTY;
+    for &dir in dirs{
+        let mut current = case.index();
+        while let Some(target) = offset(current, dir){
+            attacks.0 |= 1 << target;
+            if occupied.0 & (1 << target) != 0{
+                break
+            }
+            current = target;
+        }
+    }
+    attacks
+}
+
+/// Cases attacked by a knight on case
+pub fn knight_attacks(case: Case) -> Bitboard{
+    KNIGHT_ATTACKS[case.index()]
+}
+
+/// Cases attacked by a king on case
+pub fn king_attacks(case: Case) -> Bitboard{
+    KING_ATTACKS[case.index()]
+}
+
+/// Cases attacked by a pawn of color on case
+pub fn pawn_attacks(case: Case, color: Color) -> Bitboard{
+    PAWN_ATTACKS[color.index()][case.index()]
+}
+
+/// Cases strictly between from and to, empty if they are not on the same line, column or diagonal
+pub fn between(from: Case, to: Case) -> Bitboard{
+    BETWEEN[from.index()][to.index()]
+}
+
+/// Line, column or diagonal through from and to, empty if they are not aligned
+pub fn line(from: Case, to: Case) -> Bitboard{
+    LINE[from.index()][to.index()]
+}
+
+/// Cases attacked by a rook on case, given the occupied cases
+pub fn rook_attacks(case: Case, occupied: Bitboard) -> Bitboard{
+    magic_tables().rook_attacks(case, occupied)
+}
+
+/// Cases attacked by a bishop on case, given the occupied cases
+pub fn bishop_attacks(case: Case, occupied: Bitboard) -> Bitboard{
+    magic_tables().bishop_attacks(case, occupied)
+}
+
+/// Cases attacked by a queen on case, given the occupied cases
+pub fn queen_attacks(case: Case, occupied: Bitboard) -> Bitboard{
+    rook_attacks(case, occupied) | bishop_attacks(case, occupied)
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::engine::bitboard::{Bitboard, knight_attacks, king_attacks, pawn_attacks, rook_attacks, bishop_attacks, queen_attacks, between, line};
+    use crate::engine::board::Case;
+    use crate::engine::piece::Color;
+
+    fn set(cases: &[&str]) -> Bitboard{
+        cases.iter().fold(Bitboard::EMPTY, |set, case| set | Bitboard::from_case(Case::new_from_str(case)))
+    }
+
+    fn case(case: &str) -> Case{
+        Case::new_from_str(case)
+    }
+
+    #[test]
+    fn test_set_operations(){
+        let bb = set(&["a1", "e4", "h8"]);
+        assert_eq!(bb.count(), 3);
+        assert!(bb.contains(case("e4")));
+        assert!(!bb.contains(case("e5")));
+        assert_eq!(bb.first(), Some(case("a1")));
+        assert_eq!(bb.collect::<Vec<Case>>(), vec![case("a1"), case("e4"), case("h8")]);
+        assert_eq!(bb & !set(&["e4"]), set(&["a1", "h8"]));
+        assert_eq!(bb ^ set(&["a1", "b1"]), set(&["b1", "e4", "h8"]));
+        assert!(Bitboard::EMPTY.first().is_none());
+        assert_eq!(set(&["a1", "h8"]).to_string(), "\
+8 .......x
+7 ........
+6 ........
+5 ........
+4 ........
+3 ........
+2 ........
+1 x.......
+  abcdefgh");
+    }
+
+    #[test]
+    fn test_leaper_attacks(){
+        assert_eq!(knight_attacks(case("a1")), set(&["b3", "c2"]));
+        assert_eq!(knight_attacks(case("e4")).count(), 8);
+        assert_eq!(knight_attacks(case("h7")), set(&["f8", "f6", "g5"]));
+        assert_eq!(king_attacks(case("h1")), set(&["g1", "g2", "h2"]));
+        assert_eq!(king_attacks(case("d5")).count(), 8);
+        assert_eq!(pawn_attacks(case("e4"), Color::White), set(&["d5", "f5"]));
+        assert_eq!(pawn_attacks(case("a7"), Color::Black), set(&["b6"]));
+        assert_eq!(pawn_attacks(case("h8"), Color::White), Bitboard::EMPTY);
+    }
+
+    #[test]
+    fn test_sliding_attacks(){
+        let occupied = set(&["d6", "b4", "g1", "d2"]);
+        assert_eq!(rook_attacks(case("d4"), occupied),
+                   set(&["d5", "d6", "d3", "d2", "c4", "b4", "e4", "f4", "g4", "h4"]));
+        assert_eq!(bishop_attacks(case("d4"), occupied),
+                   set(&["c5", "b6", "a7", "e5", "f6", "g7", "h8", "c3", "b2", "a1", "e3", "f2", "g1"]));
+        assert_eq!(queen_attacks(case("d4"), occupied), rook_attacks(case("d4"), occupied) | bishop_attacks(case("d4"), occupied));
+        assert_eq!(rook_attacks(case("a1"), Bitboard::EMPTY).count(), 14);
+    }
+
+    #[test]
+    fn test_between_line(){
+        assert_eq!(between(case("a1"), case("d4")), set(&["b2", "c3"]));
+        assert_eq!(between(case("e8"), case("e5")), set(&["e7", "e6"]));
+        assert_eq!(between(case("e4"), case("f4")), Bitboard::EMPTY);
+        assert_eq!(between(case("a1"), case("b3")), Bitboard::EMPTY);
+        assert_eq!(line(case("c3"), case("e5")), set(&["a1", "b2", "c3", "d4", "e5", "f6", "g7", "h8"]));
+        assert_eq!(line(case("h2"), case("a2")).count(), 8);
+        assert_eq!(line(case("a1"), case("b3")), Bitboard::EMPTY);
+    }
+}
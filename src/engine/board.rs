@@ -1,15 +1,14 @@
 use std::fmt;
 use std::fmt::{Formatter, Display};
-use std::ops::{Index, IndexMut};
+use std::ops::Index;
 use std::str::FromStr;
-use crate::engine::piece::{Piece, Color, PieceParseError};
+use crate::engine::piece::{Piece, PieceKind, Color};
 use crate::engine::piece::Color::{White, Black};
-use std::num::ParseIntError;
+use crate::engine::error::ChessError;
 use crate::engine::moves::{Move, MoveKind};
-use crate::engine::piece::PieceKind::{Knight, Bishop, Rook, Queen, Pawn, King};
-
-#[derive(Debug, Copy, Clone)]
-pub struct ParseCastleError;
+use crate::engine::piece::PieceKind::{Rook, Pawn, King};
+use crate::engine::zobrist::KEYS;
+use crate::engine::bitboard::Bitboard;
 
 /// Represent the available castle move in a game
 #[derive(Debug, Copy, Clone)]
@@ -20,7 +19,7 @@ pub struct Castle{
     pub black_queen: bool
 }
 impl FromStr for Castle{
-    type Err = ParseCastleError;
+    type Err = ChessError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut castle = Castle{white_king: false, white_queen: false, black_king: false, black_queen: false};
@@ -33,7 +32,7 @@ impl FromStr for Castle{
                     'Q' => castle.white_queen = true,
                     'k' => castle.black_king= true,
                     'q' => castle.black_queen = true,
-                    _ => {return Err(ParseCastleError)}
+                    _ => {return Err(ChessError::Castle(car))}
                 };
             }
             Ok(castle)
@@ -73,38 +72,38 @@ pub enum Dir{
     DownLeft,
     Left,
     UpLeft,
-    Cav1,
-    Cav2,
-    Cav4,
-    Cav5,
-    Cav7,
-    Cav8,
-    Cav10,
-    Cav11
 }
-#[derive(Debug, Copy, Clone)]
-pub struct ParseCaseError;
-
 /// Represent a case of the chessboard
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Case(usize);
 impl Case{
-    pub fn new(place: usize) -> Case{
+    pub const fn new(place: usize) -> Case{
         Case(place)
     }
 
+    /// get the index of the case, from 0 for a1 to 63 for h8
+    pub const fn index(&self) -> usize{
+        self.0
+    }
+
+    /// Create a case from its name (ie: e4), panic if the name is invalid
     pub fn new_from_str(place: &str) -> Case{
-        place.parse().unwrap()
+        Case::try_from_str(place).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Create a case from its name (ie: e4)
+    pub fn try_from_str(place: &str) -> Result<Case, ChessError>{
+        place.parse()
     }
 
     /// get the line of the case
     pub fn get_line(&self) -> usize{
-        return (self.0)/8
+        (self.0)/8
     }
 
     /// get the column of the case
     pub fn get_column(&self) -> usize{
-        return (self.0)%8
+        (self.0)%8
     }
     /// return the neighbour of the case given dir and dist
     pub fn get_neighbour(&self, dir: Dir, distance: usize) -> Option<Case>{
@@ -119,7 +118,7 @@ impl Case{
                 } else {None}
             Dir::Right =>
                 if self.get_column() < (8-distance) {
-                    Some(Case(self.0+1*distance))}
+                    Some(Case(self.0+distance))}
                 else {None}
             Dir::DownRight =>
                 if self.get_line() >= (distance) && self.get_column() < (8-distance) {
@@ -135,54 +134,22 @@ impl Case{
                 else {None}
             Dir::Left =>
                 if self.get_column() >= (distance){
-                    Some(Case(self.0-1*distance))}
+                    Some(Case(self.0-distance))}
                 else {None}
             Dir::UpLeft =>
                 if self.get_line() < (8-distance) && self.get_column() >= (distance){
                     Some(Case(self.0+7*distance))}
-                else {None},
-            Dir::Cav1 =>
-                if self.get_line() < 6 && self.get_column() < 7 {
-                    Some(Case(self.0+17))
-                } else {None}
-            Dir::Cav2 =>
-                if self.get_line() < 7 && self.get_column() < 6 {
-                    Some(Case(self.0+10))
-                } else {None}
-            Dir::Cav4 =>
-                if self.get_line() >= 1 && self.get_column() < 6 {
-                    Some(Case(self.0-6))
-                } else {None}
-            Dir::Cav5 =>
-                if self.get_line() >= 2 && self.get_column() < 7 {
-                    Some(Case(self.0-15))
-                } else {None}
-            Dir::Cav7 =>
-                if self.get_line() >= 2 && self.get_column() >= 1 {
-                    Some(Case(self.0-17))
-                } else {None}
-            Dir::Cav8 =>
-                if self.get_line() >= 1 && self.get_column() >= 2 {
-                    Some(Case(self.0-10))
-                } else {None}
-            Dir::Cav10 =>
-                if self.get_line() < 7 && self.get_column() >= 2 {
-                    Some(Case(self.0+6))
-                } else {None}
-            Dir::Cav11 =>
-                if self.get_line() < 6 && self.get_column() >= 1 {
-                    Some(Case(self.0+15))
-                } else {None}
+                else {None}
         }
     }
 }
 impl FromStr for Case{
-    type Err = ParseCaseError;
+    type Err = ChessError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let chars:Vec<char> = s.chars().collect();
         if chars.len() != 2{
-            return Err(ParseCaseError)
+            return Err(ChessError::Case(s.to_string()))
         }
 
         let col = match chars[0] {  // match col
@@ -194,7 +161,7 @@ impl FromStr for Case{
             'f' => 5,
             'g' => 6,
             'h' => 7,
-            _ => {return Err(ParseCaseError);}
+            _ => {return Err(ChessError::Case(s.to_string()));}
         };
         let line = match chars[1] { // match line
             '1' => 0,
@@ -205,7 +172,7 @@ impl FromStr for Case{
             '6' => 5,
             '7' => 6,
             '8' => 7,
-            _ => {return Err(ParseCaseError);}
+            _ => {return Err(ChessError::Case(s.to_string()));}
         };
 
         Ok(Case(col + line*8))
@@ -220,27 +187,18 @@ impl fmt::Display for Case{
     }
 }
 
+/// Largest halfmove or move counter accepted in a fen
+const MAX_FEN_COUNTER: u32 = u16::MAX as u32;
+
+/// State of the board before a move, needed to take it back
 #[derive(Debug, Copy, Clone)]
-pub struct BoardParseError;
-impl From<ParseCastleError> for BoardParseError{
-    fn from(_: ParseCastleError) -> Self {
-        BoardParseError
-    }
-}
-impl From<ParseCaseError> for BoardParseError{
-    fn from(_: ParseCaseError) -> Self {
-        BoardParseError
-    }
-}
-impl From<ParseIntError> for BoardParseError{
-    fn from(_: ParseIntError) -> Self {
-        BoardParseError
-    }
-}
-impl From<PieceParseError> for BoardParseError{
-    fn from(_: PieceParseError) -> Self {
-        BoardParseError
-    }
+pub struct Undo{
+    /// Piece taken by the move, if any
+    pub captured: Option<Piece>,
+    castle: Castle,
+    en_passant: Option<Case>,
+    halfmove: u32,
+    hash: u64,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -248,7 +206,7 @@ pub struct Board{
     /// board internal representation
     /// indices start for the bottom left and got to left:
     /// ie :
-    /// ```
+    /// ```text
     ///     8  56 57 58 59 60 61 62 63
     ///     7  48 49 50 51 52 53 54 55
     ///     6  40 41 42 43 44 45 46 47
@@ -260,6 +218,10 @@ pub struct Board{
     ///        a  b  c  d  e  f  g  h
     /// ```
     board: [Option<Piece>; 64],
+    /// Cases of the pieces of each kind, indexed by PieceKind::index
+    pieces: [Bitboard; 6],
+    /// Cases of the pieces of each color, indexed by Color::index
+    colors: [Bitboard; 2],
     /// The next side to play
     pub side: Color,
     /// Castle available
@@ -270,6 +232,8 @@ pub struct Board{
     halfmove: u32,
     /// number of move in the game
     moves: u32,
+    /// Zobrist hash of the position, updated by apply_move
+    hash: u64,
 }
 impl Index<usize> for Board{
     type Output = Option<Piece>;
@@ -285,16 +249,6 @@ impl Index<&Case> for Board{
         &self.board[case.0]
     }
 }
-impl IndexMut<usize> for Board{
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        &mut self.board[index]
-    }
-}
-impl IndexMut<&Case> for Board{
-    fn index_mut(&mut self, case: &Case) -> &mut Self::Output {
-        &mut self.board[case.0]
-    }
-}
 impl fmt::Display for Board{
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let mut str = String::new();
@@ -315,64 +269,85 @@ impl fmt::Display for Board{
 /// Board can be loaded from a fen representation:
 /// (https://en.wikipedia.org/wiki/Forsyth%E2%80%93Edwards_Notation)
 impl FromStr for Board{
-    type Err = BoardParseError;
+    type Err = ChessError;
 
     fn from_str(fen: &str) -> Result<Self, Self::Err> {
         let mut board = Board::new_empty_board();
         let split_fen: Vec<&str> = fen.split(' ').collect();
 
         if split_fen.len() != 6{
-            return Err(BoardParseError);
+            return Err(ChessError::FenFieldCount(split_fen.len()));
         }
+        let invalid_field = |field: usize| ChessError::FenField{field, value: split_fen[field].to_string()};
 
         // parse fen position
+        let ranks = split_fen[0].split('/').count();
+        if ranks != 8{
+            return Err(ChessError::FenRankCount(ranks));
+        }
         for (l, line) in split_fen[0].split('/').enumerate(){
             let mut col: usize = 0;
             for car in line.chars(){
                 if let Some(num) = car.to_digit(10) {
                     col += num as usize
+                } else if col > 7{
+                    return Err(ChessError::FenRank(8 - l));
                 } else{
-                    board[col + 8*(7-l as usize)] = Some(format!("{}", car).parse()?);
+                    let piece = car.to_string().parse().map_err(|_| ChessError::FenCharacter{field: 0, character: car})?;
+                    board.set(&Case(col + 8*(7-l)), Some(piece));
                     col += 1;
                 }
             }
+            if col != 8{
+                return Err(ChessError::FenRank(8 - l));
+            }
         }
 
         // parse side
         match split_fen[1]{
             "w" => board.side = White,
             "b" => board.side = Black,
-            _ => return Err(BoardParseError)
+            _ => return Err(invalid_field(1))
         }
 
         // parse castle
-        board.castle = split_fen[2].parse()?;
+        board.castle = split_fen[2].parse().map_err(|err| match err {
+            ChessError::Castle(character) => ChessError::FenCharacter{field: 2, character},
+            err => err,
+        })?;
 
         // parse 'en passant'
         if split_fen[3] == "-"{
             board.en_passant = None
         } else{
-            board.en_passant = Some(split_fen[3].parse()?)
+            board.en_passant = Some(split_fen[3].parse().map_err(|_| invalid_field(3))?)
         }
 
-        // parse halfmove
-        board.halfmove = split_fen[4].parse()?;
-
-        // parse move
-        board.moves = split_fen[5].parse()?;
+        // parse halfmove and move, bounded so that playing moves can not overflow them
+        let parse_counter = |field: usize| split_fen[field].parse().ok()
+            .filter(|&counter| counter <= MAX_FEN_COUNTER)
+            .ok_or_else(|| invalid_field(field));
+        board.halfmove = parse_counter(4)?;
+        board.moves = parse_counter(5)?;
 
+        board.hash = board.compute_hash();
         Ok(board)
     }
 }
 impl Board{
     /// Create a new board with no pieces.
     pub fn new_empty_board() -> Self{
-        Board{board: [None; 64],
+        let mut board = Board{board: [None; 64],
+            pieces: [Bitboard::EMPTY; 6],
+            colors: [Bitboard::EMPTY; 2],
             side:White,
             castle: "QKqk".parse().unwrap(),
             en_passant:None,
             halfmove:0,
-            moves:1}
+            moves:1,
+            hash: 0};
+        board.hash = board.compute_hash();
+        board
     }
 
     /// Create a new  board with starting position.
@@ -380,13 +355,63 @@ impl Board{
         Board::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
     }
 
-    /// Create a new board from a fen repressentation:
+    /// Create a new board from a fen repressentation, panic if the fen is invalid:
     /// (https://en.wikipedia.org/wiki/Forsyth%E2%80%93Edwards_Notation)
     pub fn new_from_fen(fen: &str) -> Self{
-        fen.parse().unwrap()
+        Board::try_from_fen(fen).unwrap_or_else(|err| panic!("{}: {}", fen, err))
     }
 
-    pub fn to_fen(&self) -> String{
+    /// Create a new board from a fen repressentation.
+    /// Only the syntax is checked, see validate for the position itself
+    pub fn try_from_fen(fen: &str) -> Result<Self, ChessError>{
+        fen.parse()
+    }
+
+    /// Check that the position can be played: one king of each color, no pawn on the first or
+    /// last rank, castle rights and en passant case matching the pieces and the side not to move
+    /// not in check. Return the first problem found
+    pub fn validate(&self) -> Result<(), ChessError>{
+        for color in [White, Black]{
+            let count = self.pieces(King, color).count();
+            if count != 1{
+                return Err(ChessError::KingCount{color, count});
+            }
+        }
+        let pawns = self.pieces[Pawn.index()];
+        if let Some(case) = (0..8).chain(56..64).map(Case).find(|case| pawns.contains(*case)){
+            return Err(ChessError::PawnOnBackRank(case));
+        }
+        let rights = [
+            (self.castle.white_king, 'K', White, 4, 7),
+            (self.castle.white_queen, 'Q', White, 4, 0),
+            (self.castle.black_king, 'k', Black, 60, 63),
+            (self.castle.black_queen, 'q', Black, 60, 56),
+        ];
+        for (right, car, color, king, rook) in rights{
+            if right && !(self.pieces(King, color).contains(Case(king)) && self.pieces(Rook, color).contains(Case(rook))){
+                return Err(ChessError::CastleRights(car));
+            }
+        }
+        if let Some(case) = self.en_passant{
+            // the pawn of the other side has just moved two cases, over the en passant case
+            let (line, push, back) = match self.side {
+                White => (5, Dir::Down, Dir::Up),
+                Black => (2, Dir::Up, Dir::Down),
+            };
+            let pushed = case.get_neighbour(push, 1);
+            let start = case.get_neighbour(back, 1);
+            if case.get_line() != line || self[&case].is_some() || start.is_some_and(|start| self[&start].is_some())
+                || !pushed.is_some_and(|pushed| self.pieces(Pawn, self.side.flip()).contains(pushed)){
+                return Err(ChessError::EnPassant(case));
+            }
+        }
+        if self.king_square(&self.side.flip()).is_some_and(|king| !self.attackers_to(&king, &self.side).is_empty()){
+            return Err(ChessError::OpponentInCheck);
+        }
+        Ok(())
+    }
+
+    pub fn to_fen(self) -> String{
         let mut res = String::new();
         for line in (0..8).rev(){
             let mut n_empty = 0;
@@ -404,7 +429,7 @@ impl Board{
                 }
             }
             match n_empty {
-                0 => res.push_str(r"/"),
+                0 => res.push('/'),
                 val => res.push_str(&format!("{}/",val))
             }
         }
@@ -420,137 +445,177 @@ impl Board{
         res
     }
 
+    /// Zobrist hash of the position
+    pub fn hash(&self) -> u64{
+        self.hash
+    }
+
+    /// Cases of the pieces of a kind and color
+    pub fn pieces(&self, kind: PieceKind, color: Color) -> Bitboard{
+        self.pieces[kind.index()] & self.colors[color.index()]
+    }
+
+    /// Cases of the pieces of a color
+    pub fn color_pieces(&self, color: Color) -> Bitboard{
+        self.colors[color.index()]
+    }
+
+    /// Cases with a piece
+    pub fn occupied(&self) -> Bitboard{
+        self.colors[0] | self.colors[1]
+    }
+
+    /// Put a piece (or nothing) on a case, update the bitboards and the hash
+    fn set(&mut self, case: &Case, piece: Option<Piece>){
+        let bit = Bitboard::from_case(*case);
+        if let Some(old) = self.board[case.0]{
+            self.hash ^= KEYS.piece(&old, case);
+            self.pieces[old.kind.index()] &= !bit;
+            self.colors[old.color.index()] &= !bit;
+        }
+        if let Some(new) = piece{
+            self.hash ^= KEYS.piece(&new, case);
+            self.pieces[new.kind.index()] |= bit;
+            self.colors[new.color.index()] |= bit;
+        }
+        self.board[case.0] = piece;
+    }
+
     /// Apply a move and return a new board
     pub fn apply_move(&self, mv: &Move) -> Self{
-        let mut new = self.clone();
-        new.en_passant = None;
+        let mut new = *self;
+        new.make_move(mv);
+        new
+    }
+
+    /// Play a move on the board, return what is needed to take it back with unmake_move
+    pub fn make_move(&mut self, mv: &Move) -> Undo{
+        let piece = self[&mv.from()].unwrap_or_else(|| panic!("no piece to move for {} on {}", mv, self.to_fen()));
+        let mut undo = Undo{captured: None, castle: self.castle, en_passant: self.en_passant, halfmove: self.halfmove, hash: self.hash};
+        self.en_passant = None;
         match mv.get_kind() {
             MoveKind::DoublePawnPush => {
-                new[&mv.to] = new[&mv.from];
-                new[&mv.from] = None;
-                new.en_passant = match mv.from.get_line() {
-                    1 => mv.from.get_neighbour(Dir::Up, 1),
-                    6 => mv.from.get_neighbour(Dir::Down, 1),
-                    _ => {panic!("invalid mv {}", mv); Some(mv.from)}
+                self.move_piece(&mv.from(), &mv.to());
+                self.en_passant = match mv.from().get_line() {
+                    1 => mv.from().get_neighbour(Dir::Up, 1),
+                    6 => mv.from().get_neighbour(Dir::Down, 1),
+                    _ => panic!("invalid mv {}", mv)
                 }
             }
-            MoveKind::KingCastle => {
-                new[&mv.to] = new[&mv.from]; // move the king
-                // move the tower
-                let rock_target = &mv.to.get_neighbour(Dir::Left, 1).unwrap();
-                let rock_source = &mv.to.get_neighbour(Dir::Right, 1).unwrap();
-                match self.side {
-                    White => new.castle.white_king = false,
-                    Black => new.castle.black_king = false,
-                }
-                new[rock_target] = new[rock_source];
-                new[&mv.from] = None;
-                new[rock_source] = None;
-            }
-            MoveKind::QueenCastle => {
-                new[&mv.to] = new[&mv.from]; // move the king
-                // move the tower
-                let rock_target = &mv.to.get_neighbour(Dir::Right, 1).unwrap();
-                let rock_source = &mv.to.get_neighbour(Dir::Left, 2).unwrap();
-                match self.side {
-                    White => new.castle.white_queen = false,
-                    Black => new.castle.black_queen = false,
-                }
-                new[rock_target] = new[rock_source];
-                new[&mv.from] = None;
-                new[rock_source] = None;
+            MoveKind::KingCastle | MoveKind::QueenCastle => {
+                self.move_piece(&mv.from(), &mv.to()); // move the king
+                let (rock_source, rock_target) = Board::castle_rock_cases(mv);
+                self.move_piece(&rock_source, &rock_target);
             }
             MoveKind::EnPassantCapture => {
-                new[&mv.to] = new[&mv.from];
-                new[&mv.from] = None;
                 // remove the taken pawn
-                match mv.to.get_line() {
-                    2 => new[&mv.to.get_neighbour(Dir::Up, 1).unwrap()] = None,
-                    5 => new[&mv.to.get_neighbour(Dir::Down, 1).unwrap()] = None,
-                    _ => {panic!("invalid mv {}", mv)}
-                }
+                let taken = Board::en_passant_taken_case(mv);
+                undo.captured = self[&taken];
+                self.set(&taken, None);
+                self.move_piece(&mv.from(), &mv.to());
             }
-            MoveKind::KnightPromotion => {
-                new[&mv.to] = Some(Piece{kind: Knight, color:new[&mv.from].unwrap().color});
-                new[&mv.from] = None;
-            }
-            MoveKind::BishopPromotion => {
-                new[&mv.to] = Some(Piece{kind: Bishop, color:new[&mv.from].unwrap().color});
-                new[&mv.from] = None;
-            }
-            MoveKind::RookPromotion => {
-                new[&mv.to] = Some(Piece{kind: Rook, color:new[&mv.from].unwrap().color});
-                new[&mv.from] = None;
-            }
-            MoveKind::QueenPromotion => {
-                new[&mv.to] = Some(Piece{kind: Queen, color:new[&mv.from].unwrap().color});
-                new[&mv.from] = None;
-            }
-            MoveKind::KnightCapturePromotion => {
-                new[&mv.to] = Some(Piece{kind: Knight, color:new[&mv.from].unwrap().color});
-                new[&mv.from] = None;
-            }
-            MoveKind::BishopCapturePromotion => {
-                new[&mv.to] = Some(Piece{kind: Bishop, color:new[&mv.from].unwrap().color});
-                new[&mv.from] = None;
-            }
-            MoveKind::RookCapturePromotion => {
-                new[&mv.to] = Some(Piece{kind: Rook, color:new[&mv.from].unwrap().color});
-                new[&mv.from] = None;
-            }
-            MoveKind::QueenCapturePromotion => {
-                new[&mv.to] = Some(Piece{kind: Queen, color:new[&mv.from].unwrap().color});
-                new[&mv.from] = None;
-            }
-            _ => { // Default are Quiet ant simple capture
-                new[&mv.to] = new[&mv.from];
-                new[&mv.from] = None;
+            _ => { // Quiet, simple capture and promotion
+                undo.captured = self[&mv.to()];
+                self.move_piece(&mv.from(), &mv.to());
+                if let Some(kind) = mv.get_promotion(){
+                    self.set(&mv.to(), Some(Piece{kind, color: piece.color}));
+                }
             },
         }
-        if self[&mv.from].is_none(){
-            println!("{}", mv);
-            println!("{}", self.to_fen())
-        }
-        match mv.is_capture() || self[&mv.from].unwrap().kind == Pawn{
-            true => new.halfmove = 0,
-            false => new.halfmove += 1
+        match mv.is_capture() || piece.kind == Pawn{
+            true => self.halfmove = 0,
+            false => self.halfmove += 1
         };
-        if self[&mv.from].unwrap().kind == Rook {
-            match &mv.from.0 {
-                0 => new.castle.white_queen = false,
-                7 => new.castle.white_king = false,
-                56 => new.castle.black_queen = false,
-                63 => new.castle.black_king = false,
-                _ => {}
-            };
-        } else if self[&mv.from].unwrap().kind == King{
-            match &mv.from.0 {
-                4 => {new.castle.white_queen = false; new.castle.white_king = false}
-                60 => {new.castle.black_king = false; new.castle.black_queen = false}
-                _ => {}
+        if piece.kind == Rook {
+            self.remove_castle(&mv.from());
+        } else if piece.kind == King{
+            match piece.color {
+                White => {self.castle.white_queen = false; self.castle.white_king = false}
+                Black => {self.castle.black_king = false; self.castle.black_queen = false}
             };
         };
         if mv.is_capture() {
-            match &mv.to.0 {
-                0 => new.castle.white_queen = false,
-                7 => new.castle.white_king = false,
-                56 => new.castle.black_queen = false,
-                63 => new.castle.black_king = false,
-                _ => {}
-            };
+            self.remove_castle(&mv.to());
         }
         match self.side {
-            Color::White => new.side = Color::Black,
-            Color::Black => {new.side = Color::White; new.moves += 1}
+            Color::White => self.side = Color::Black,
+            Color::Black => {self.side = Color::White; self.moves += 1}
         }
-        new
+        self.hash ^= KEYS.side(Color::White) ^ KEYS.side(Color::Black)
+            ^ KEYS.castle(&undo.castle) ^ KEYS.castle(&self.castle)
+            ^ KEYS.en_passant(undo.en_passant) ^ KEYS.en_passant(self.en_passant);
+        undo
+    }
+
+    /// Take back a move played with make_move
+    pub fn unmake_move(&mut self, mv: &Move, undo: &Undo){
+        self.side = self.side.flip();
+        if self.side == Black{
+            self.moves -= 1;
+        }
+        let piece = self[&mv.to()].unwrap_or_else(|| panic!("no piece to take back for {} on {}", mv, self.to_fen()));
+        let kind = if mv.is_promotion() {Pawn} else {piece.kind};
+        self.set(&mv.from(), Some(Piece{kind, color: piece.color}));
+        match mv.get_kind() {
+            MoveKind::KingCastle | MoveKind::QueenCastle => {
+                self.set(&mv.to(), None);
+                let (rock_source, rock_target) = Board::castle_rock_cases(mv);
+                self.move_piece(&rock_target, &rock_source);
+            }
+            MoveKind::EnPassantCapture => {
+                self.set(&mv.to(), None);
+                self.set(&Board::en_passant_taken_case(mv), undo.captured);
+            }
+            _ => self.set(&mv.to(), undo.captured),
+        }
+        self.castle = undo.castle;
+        self.en_passant = undo.en_passant;
+        self.halfmove = undo.halfmove;
+        self.hash = undo.hash;
+    }
+
+    /// Move the piece on from to the case to, removing the piece on it if any
+    fn move_piece(&mut self, from: &Case, to: &Case){
+        self.set(to, self[from]);
+        self.set(from, None);
+    }
+
+    /// Cases the rook leaves and reaches when castling
+    pub(crate) fn castle_rock_cases(mv: &Move) -> (Case, Case){
+        match mv.get_kind() {
+            MoveKind::KingCastle => (mv.to().get_neighbour(Dir::Right, 1).unwrap(), mv.to().get_neighbour(Dir::Left, 1).unwrap()),
+            _ => (mv.to().get_neighbour(Dir::Left, 2).unwrap(), mv.to().get_neighbour(Dir::Right, 1).unwrap()),
+        }
+    }
+
+    /// Case of the pawn taken 'en passant'
+    pub(crate) fn en_passant_taken_case(mv: &Move) -> Case{
+        match mv.to().get_line() {
+            2 => mv.to().get_neighbour(Dir::Up, 1).unwrap(),
+            5 => mv.to().get_neighbour(Dir::Down, 1).unwrap(),
+            _ => panic!("invalid mv {}", mv)
+        }
+    }
+
+    /// Remove the castle using the rook starting on case, the rook moved or was taken
+    fn remove_castle(&mut self, case: &Case){
+        match case.0 {
+            0 => self.castle.white_queen = false,
+            7 => self.castle.white_king = false,
+            56 => self.castle.black_queen = false,
+            63 => self.castle.black_king = false,
+            _ => {}
+        };
     }
 }
 
 #[cfg(test)]
 mod tests{
     use crate::engine::board::{Board, Case};
-    use crate::engine::moves::{Move, MoveKind};
+    use crate::engine::moves::Move;
+    use crate::engine::error::ChessError;
+    use crate::engine::piece::PieceKind::{King, Queen, Rook, Bishop, Knight, Pawn};
+    use crate::engine::piece::Color::{White, Black};
 
     #[test]
     fn debug_board(){
@@ -635,6 +700,137 @@ mod tests{
         assert_eq!(Board::new_from_fen(fen).to_fen(), fen);
     }
 
+    #[test]
+    fn fen_errors(){
+        let errors = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -", ChessError::FenFieldCount(4)),
+            ("rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", ChessError::FenRankCount(7)),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNRR w KQkq - 0 1", ChessError::FenRank(1)),
+            ("rnbqkbnr/pppppppp/8/8/8/7/PPPPPPPP/RNBQKBNR w KQkq - 0 1", ChessError::FenRank(3)),
+            ("rnbqkbnr/pppppppp/8/8/8/9/PPPPPPPP/RNBQKBNR w KQkq - 0 1", ChessError::FenRank(3)),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQXBNR w KQkq - 0 1", ChessError::FenCharacter{field: 0, character: 'X'}),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1", ChessError::FenField{field: 1, value: "x".to_string()}),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQxq - 0 1", ChessError::FenCharacter{field: 2, character: 'x'}),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e9 0 1", ChessError::FenField{field: 3, value: "e9".to_string()}),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - a 1", ChessError::FenField{field: 4, value: "a".to_string()}),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 -1", ChessError::FenField{field: 5, value: "-1".to_string()}),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 100000 1", ChessError::FenField{field: 4, value: "100000".to_string()}),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 4294967295", ChessError::FenField{field: 5, value: "4294967295".to_string()}),
+        ];
+        for (fen, error) in errors.iter(){
+            assert_eq!(Board::try_from_fen(fen).err().as_ref(), Some(error), "{}", fen);
+        }
+        assert_eq!(ChessError::FenCharacter{field: 0, character: 'X'}.to_string(), "unexpected character 'X' in fen field 0");
+    }
+
+    #[test]
+    fn test_validate(){
+        let valid = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+            "4k3/8/8/8/8/8/8/4K2R w K - 0 1",
+            "4k3/8/8/8/8/8/8/4K2r w - - 0 1",
+        ];
+        for fen in valid.iter(){
+            assert_eq!(Board::new_from_fen(fen).validate(), Ok(()), "{}", fen);
+        }
+        let invalid = [
+            ("8/8/8/8/8/8/8/4K3 w - - 0 1", ChessError::KingCount{color: Black, count: 0}),
+            ("4k3/8/8/8/8/8/8/3KK3 w - - 0 1", ChessError::KingCount{color: White, count: 2}),
+            ("4k3/8/8/8/8/8/8/P3K3 w - - 0 1", ChessError::PawnOnBackRank(Case::new_from_str("a1"))),
+            ("3pk3/8/8/8/8/8/8/4K3 w - - 0 1", ChessError::PawnOnBackRank(Case::new_from_str("d8"))),
+            ("4k3/8/8/8/8/8/8/4K3 w K - 0 1", ChessError::CastleRights('K')),
+            ("4k3/8/8/8/8/8/8/R4K2 w Q - 0 1", ChessError::CastleRights('Q')),
+            ("r3k3/8/8/8/8/8/8/4K3 w kq - 0 1", ChessError::CastleRights('k')),
+            ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e3 0 1", ChessError::EnPassant(Case::new_from_str("e3"))),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq e3 0 1", ChessError::EnPassant(Case::new_from_str("e3"))),
+            ("rnbqkbnr/pppppppp/8/8/4P3/4P3/PPP2PPP/RNBQKBNR b KQkq e3 0 1", ChessError::EnPassant(Case::new_from_str("e3"))),
+            ("4k3/8/8/8/8/8/8/4K2r b - - 0 1", ChessError::OpponentInCheck),
+        ];
+        for (fen, error) in invalid.iter(){
+            assert_eq!(Board::new_from_fen(fen).validate().err().as_ref(), Some(error), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn case_errors(){
+        assert_eq!(Case::try_from_str("e4"), Ok(Case::new_from_str("e4")));
+        for case in ["", "e", "e44", "i4", "e0", "4e"].iter(){
+            assert_eq!(Case::try_from_str(case), Err(ChessError::Case(case.to_string())));
+        }
+    }
+
+    /// Check the bitboards against the cases of the board
+    fn check_bitboards(board: &Board){
+        for i in 0..64{
+            let case = Case::new(i);
+            for kind in [King, Queen, Rook, Bishop, Knight, Pawn]{
+                for color in [White, Black]{
+                    let expected = matches!(board[i], Some(piece) if piece.kind == kind && piece.color == color);
+                    assert_eq!(board.pieces(kind, color).contains(case), expected, "{} {}", case, board.to_fen());
+                }
+            }
+            assert_eq!(board.occupied().contains(case), board[i].is_some());
+        }
+    }
+
+    #[test]
+    fn test_bitboards(){
+        let mut board = Board::new_board();
+        check_bitboards(&board);
+        assert_eq!(board.pieces(Pawn, White).count(), 8);
+        assert_eq!(board.pieces(King, Black).first(), Some(Case::new_from_str("e8")));
+        assert_eq!(board.color_pieces(Black).count(), 16);
+
+        // capture, castle, promotion and 'en passant'
+        board = Board::new_from_fen("4k2r/pPp3P1/8/1P6/5p2/8/1P2P3/R3K3 w Qk - 0 1");
+        for mv in ["e2e4", "f4e3", "e1c1", "e8g8", "b7b8q", "a7a5", "b5a6", "f8f7", "g7h8q"].iter(){
+            board = board.apply_move(&Move::new_on_board(mv, &board));
+            check_bitboards(&board);
+        }
+        assert_eq!(board.to_fen(), "1Q4kQ/2p2r2/P7/8/8/4p3/1P6/2KR4 b - - 0 5");
+    }
+
+    /// Play and take back every move of the tree, the board must be restored each time
+    fn check_unmake(board: &mut Board, depth: u32){
+        if depth == 0{
+            return
+        }
+        let (fen, hash) = (board.to_fen(), board.hash());
+        for mv in board.get_moves(){
+            let undo = board.make_move(&mv);
+            assert_eq!(board.to_fen(), Board::new_from_fen(&fen).apply_move(&mv).to_fen());
+            check_unmake(board, depth - 1);
+            board.unmake_move(&mv, &undo);
+            assert_eq!(board.to_fen(), fen, "{}", mv);
+            assert_eq!(board.hash(), hash);
+        }
+        check_bitboards(board);
+    }
+
+    #[test]
+    fn test_unmake_move(){
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        ].iter(){
+            check_unmake(&mut Board::new_from_fen(fen), 3);
+        }
+
+        let mut board = Board::new_from_fen("4k2r/pPp3P1/8/1P6/5p2/8/1P2P3/R3K3 w Qk - 0 1");
+        let mv = Move::new_on_board("b7b8n", &board);
+        let undo = board.make_move(&mv);
+        assert!(undo.captured.is_none());
+        board.unmake_move(&mv, &undo);
+        let mv = Move::new_on_board("g7h8q", &board);
+        let undo = board.make_move(&mv);
+        assert_eq!(undo.captured.map(|piece| piece.to_string()), Some("r".to_string()));
+        board.unmake_move(&mv, &undo);
+        assert_eq!(board.to_fen(), "4k2r/pPp3P1/8/1P6/5p2/8/1P2P3/R3K3 w Qk - 0 1");
+    }
+
     #[test]
     fn test_move_simple(){
         // see https://lichess.org/editor/4k2r/pPp3P1/8/1P6/5p2/8/1P2P3/R3K3_w_Qk_-_0_1
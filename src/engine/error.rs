@@ -0,0 +1,73 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Formatter;
+use crate::engine::board::Case;
+use crate::engine::piece::Color;
+
+/// Error returned when a position, a case, a piece or a move can not be read or played.
+/// The fields of a fen are numbered from 0: placement, side, castle, en passant, halfmove, move
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ChessError{
+    /// A fen has 6 fields separated by spaces, gives the number found
+    FenFieldCount(usize),
+    /// The placement of a fen does not have 8 ranks, gives the number found
+    FenRankCount(usize),
+    /// A rank of the placement does not have 8 cases, gives the rank from 1 to 8
+    FenRank(usize),
+    /// Unexpected character in a field of a fen
+    FenCharacter{field: usize, character: char},
+    /// The value of a field of a fen is invalid
+    FenField{field: usize, value: String},
+    /// Not a case name such as `e4`
+    Case(String),
+    /// Not a piece letter such as `K` or `p`
+    Piece(String),
+    /// Not a castle letter: `K`, `Q`, `k` or `q`
+    Castle(char),
+    /// Not a move in uci notation such as `e2e4` or `a7a8q`, or in algebraic notation such as `Nf3`
+    MoveSyntax(String),
+    /// The move can not be played on the board
+    IllegalMove(String),
+    /// More than one legal move matches the notation
+    AmbiguousMove(String),
+    /// A color does not have exactly one king
+    KingCount{color: Color, count: u32},
+    /// A pawn is on the first or last rank
+    PawnOnBackRank(Case),
+    /// A castle right without the king and the rook on their starting cases
+    CastleRights(char),
+    /// The en passant case does not follow a double pawn push
+    EnPassant(Case),
+    /// The king of the side not to move can be taken
+    OpponentInCheck,
+}
+impl fmt::Display for ChessError{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ChessError::FenFieldCount(count) => write!(f, "expected 6 fen fields, found {}", count),
+            ChessError::FenRankCount(count) => write!(f, "expected 8 ranks, found {}", count),
+            ChessError::FenRank(rank) => write!(f, "rank {} does not have 8 cases", rank),
+            ChessError::FenCharacter{field, character} =>
+                write!(f, "unexpected character '{}' in fen field {}", character, field),
+            ChessError::FenField{field, value} => write!(f, "invalid value '{}' for fen field {}", value, field),
+            ChessError::Case(case) => write!(f, "invalid case '{}'", case),
+            ChessError::Piece(piece) => write!(f, "invalid piece '{}'", piece),
+            ChessError::Castle(castle) => write!(f, "invalid castle '{}'", castle),
+            ChessError::MoveSyntax(mv) => write!(f, "invalid move '{}'", mv),
+            ChessError::IllegalMove(mv) => write!(f, "illegal move '{}'", mv),
+            ChessError::AmbiguousMove(mv) => write!(f, "ambiguous move '{}'", mv),
+            ChessError::KingCount{color, count} => {
+                let color = match color {
+                    Color::White => "white",
+                    Color::Black => "black",
+                };
+                write!(f, "expected one {} king, found {}", color, count)
+            }
+            ChessError::PawnOnBackRank(case) => write!(f, "pawn on the back rank in {}", case),
+            ChessError::CastleRights(castle) => write!(f, "castle right '{}' without the king and the rook on their cases", castle),
+            ChessError::EnPassant(case) => write!(f, "impossible en passant case {}", case),
+            ChessError::OpponentInCheck => write!(f, "the side not to move is in check"),
+        }
+    }
+}
+impl Error for ChessError{}
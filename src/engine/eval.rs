@@ -0,0 +1,141 @@
+use crate::engine::board::{Board, Case};
+use crate::engine::piece::{Piece, PieceKind, Color};
+
+/// Material value of each piece kind in centipawns
+pub fn piece_value(kind: PieceKind) -> i32{
+    match kind {
+        PieceKind::Pawn => 100,
+        PieceKind::Knight => 320,
+        PieceKind::Bishop => 330,
+        PieceKind::Rook => 500,
+        PieceKind::Queen => 900,
+        PieceKind::King => 20_000,
+    }
+}
+
+// Piece square tables, written from white point of view with the 8th rank on top
+// (see https://www.chessprogramming.org/Simplified_Evaluation_Function)
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+#[rustfmt::skip]
+const KING_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+/// Positional bonus of a piece standing on case
+fn square_value(piece: &Piece, case: &Case) -> i32{
+    // tables are written rank 8 first, mirror the line for white
+    let line = match piece.color {
+        Color::White => 7 - case.get_line(),
+        Color::Black => case.get_line(),
+    };
+    let index = line * 8 + case.get_column();
+    match piece.kind {
+        PieceKind::Pawn => PAWN_TABLE[index],
+        PieceKind::Knight => KNIGHT_TABLE[index],
+        PieceKind::Bishop => BISHOP_TABLE[index],
+        PieceKind::Rook => ROOK_TABLE[index],
+        PieceKind::Queen => QUEEN_TABLE[index],
+        PieceKind::King => KING_TABLE[index],
+    }
+}
+
+/// Static evaluation of the board in centipawns, from the point of view of the side to move
+pub fn evaluate(board: &Board) -> i32{
+    let mut score = 0;
+    for i in 0..64{
+        if let Some(piece) = board[i]{
+            let value = piece_value(piece.kind) + square_value(&piece, &Case::new(i));
+            if piece.color == board.side {
+                score += value
+            } else {
+                score -= value
+            }
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::engine::board::Board;
+    use crate::engine::eval::evaluate;
+
+    #[test]
+    fn test_eval_symmetry(){
+        assert_eq!(evaluate(&Board::new_board()), 0);
+        // same position with colors mirrored
+        let white = Board::new_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let black = Board::new_from_fen("r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1");
+        assert_eq!(evaluate(&white), evaluate(&black));
+    }
+
+    #[test]
+    fn test_eval_material(){
+        // white is a queen up
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        assert!(evaluate(&board) > 800);
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 1");
+        assert!(evaluate(&board) < -800);
+    }
+}
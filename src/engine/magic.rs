@@ -0,0 +1,170 @@
+use std::sync::OnceLock;
+use crate::engine::bitboard::{Bitboard, sliding_attacks, ROOK_DIRS, BISHOP_DIRS};
+use crate::engine::board::Case;
+use crate::engine::zobrist::next_random;
+
+/// Seeds of the search of the magic numbers for each line of the board, fixed so the tables are
+/// the same on every run. With this generator they find all the magics quickly
+/// (they are the seeds used by Stockfish)
+const SEEDS: [u64; 8] = [728, 10316, 55013, 32803, 12281, 15100, 16645, 255];
+const RANK_1: u64 = 0xFF;
+const RANK_8: u64 = 0xFF << 56;
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = FILE_A << 7;
+
+/// Hash the cases of the mask occupied by a piece into the attack table:
+/// index = offset + ((occupied & mask) * magic) >> shift
+/// (https://www.chessprogramming.org/Magic_Bitboards)
+#[derive(Debug, Copy, Clone, Default)]
+struct Magic{
+    /// Cases whose occupation changes the attacks, the edges of the board are excluded
+    mask: Bitboard,
+    magic: u64,
+    shift: u32,
+    /// Start of the attacks of this case in the shared table
+    offset: usize,
+}
+impl Magic{
+    fn index(&self, occupied: Bitboard) -> usize{
+        self.offset + ((occupied & self.mask).0.wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+/// Attacks of the sliding pieces for every case and occupation
+pub struct MagicTables{
+    rook: [Magic; 64],
+    bishop: [Magic; 64],
+    attacks: Vec<Bitboard>,
+}
+impl MagicTables{
+    /// Search the magic numbers and fill the attack table
+    fn generate() -> Self{
+        let mut attacks = Vec::new();
+        let rook = MagicTables::generate_piece(&ROOK_DIRS, &mut attacks);
+        let bishop = MagicTables::generate_piece(&BISHOP_DIRS, &mut attacks);
+        MagicTables{rook, bishop, attacks}
+    }
+
+    fn generate_piece(dirs: &[(isize, isize)], attacks: &mut Vec<Bitboard>) -> [Magic; 64]{
+        let mut magics = [Magic::default(); 64];
+        for (index, magic) in magics.iter_mut().enumerate(){
+            let case = Case::new(index);
+            *magic = find_magic(case, dirs, attacks, SEEDS[case.get_line()]);
+        }
+        magics
+    }
+
+    /// Cases attacked by a rook on case, given the occupied cases
+    pub fn rook_attacks(&self, case: Case, occupied: Bitboard) -> Bitboard{
+        self.attacks[self.rook[case.index()].index(occupied)]
+    }
+
+    /// Cases attacked by a bishop on case, given the occupied cases
+    pub fn bishop_attacks(&self, case: Case, occupied: Bitboard) -> Bitboard{
+        self.attacks[self.bishop[case.index()].index(occupied)]
+    }
+}
+
+static TABLES: OnceLock<MagicTables> = OnceLock::new();
+
+/// Get the magic tables, they are generated on the first call
+pub fn magic_tables() -> &'static MagicTables{
+    TABLES.get_or_init(MagicTables::generate)
+}
+
+/// Cases of the rays from case whose occupation matters: the last case of each ray is always
+/// attacked, whether it is occupied or not
+fn relevant_mask(case: Case, dirs: &[(isize, isize)]) -> Bitboard{
+    let line = RANK_1 << (8 * case.get_line());
+    let column = FILE_A << case.get_column();
+    let edges = ((RANK_1 | RANK_8) & !line) | ((FILE_A | FILE_H) & !column);
+    sliding_attacks(case, Bitboard::EMPTY, dirs) & !Bitboard(edges)
+}
+
+/// Random number with few bits set, they make better magic candidates
+fn sparse_random(state: &mut u64) -> u64{
+    let mut random = u64::MAX;
+    for _ in 0..3{
+        let (next, value) = next_random(*state);
+        *state = next;
+        random &= value;
+    }
+    random
+}
+
+/// Find a magic number mapping every occupation of the mask of case without harmful collision,
+/// and append the attacks of case to the table
+fn find_magic(case: Case, dirs: &[(isize, isize)], attacks: &mut Vec<Bitboard>, seed: u64) -> Magic{
+    let mask = relevant_mask(case, dirs);
+    let shift = 64 - mask.count();
+
+    // enumerate all the subsets of the mask (carry-rippler)
+    let mut occupations = Vec::new();
+    let mut subset = 0u64;
+    loop {
+        occupations.push((Bitboard(subset), sliding_attacks(case, Bitboard(subset), dirs)));
+        subset = subset.wrapping_sub(mask.0) & mask.0;
+        if subset == 0{
+            break
+        }
+    }
+
+    // an entry is only used by the candidate it was written by, so the table is not cleared
+    // between two candidates
+    let mut table = vec![(0u32, Bitboard::EMPTY); occupations.len()];
+    let mut candidate = 0;
+    let mut state = seed;
+    loop {
+        let magic = sparse_random(&mut state);
+        if (mask.0.wrapping_mul(magic) >> 56).count_ones() < 6{
+            continue
+        }
+        candidate += 1;
+        // two occupations may share an index only if they have the same attacks
+        let found = occupations.iter().all(|(occupied, expected)| {
+            let entry = &mut table[(occupied.0.wrapping_mul(magic) >> shift) as usize];
+            if entry.0 != candidate{
+                *entry = (candidate, *expected);
+            }
+            entry.1 == *expected
+        });
+        if found{
+            let offset = attacks.len();
+            attacks.extend(table.iter().map(|(_, attack)| *attack));
+            return Magic{mask, magic, shift, offset}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::engine::bitboard::{Bitboard, sliding_attacks, ROOK_DIRS, BISHOP_DIRS};
+    use crate::engine::board::Case;
+    use crate::engine::magic::{magic_tables, relevant_mask};
+    use crate::engine::zobrist::next_random;
+
+    #[test]
+    fn test_relevant_mask(){
+        assert_eq!(relevant_mask(Case::new_from_str("a1"), &ROOK_DIRS).count(), 12);
+        assert_eq!(relevant_mask(Case::new_from_str("e4"), &ROOK_DIRS).count(), 10);
+        assert_eq!(relevant_mask(Case::new_from_str("e4"), &BISHOP_DIRS).count(), 9);
+        assert_eq!(relevant_mask(Case::new_from_str("h8"), &BISHOP_DIRS).count(), 6);
+    }
+
+    #[test]
+    fn test_magic_attacks(){
+        let tables = magic_tables();
+        let mut state = 1;
+        for index in 0..64{
+            let case = Case::new(index);
+            for _ in 0..200{
+                let (next, random) = next_random(state);
+                let (next, random_2) = next_random(next);
+                state = next;
+                let occupied = Bitboard(random & random_2);
+                assert_eq!(tables.rook_attacks(case, occupied), sliding_attacks(case, occupied, &ROOK_DIRS));
+                assert_eq!(tables.bishop_attacks(case, occupied), sliding_attacks(case, occupied, &BISHOP_DIRS));
+            }
+        }
+    }
+}
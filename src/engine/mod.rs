@@ -1,4 +1,15 @@
 pub mod board;
+pub mod bitboard;
+pub(crate) mod magic;
 pub mod piece;
 pub mod moves;
-mod move_generation;
\ No newline at end of file
+pub mod move_list;
+pub mod error;
+pub mod eval;
+pub mod perft;
+pub mod san;
+pub mod search;
+pub mod time_manager;
+pub(crate) mod transposition;
+pub(crate) mod zobrist;
+pub mod move_generation;
\ No newline at end of file
@@ -1,336 +1,417 @@
-#![feature(test)]
 use crate::engine::board::{Board, Case, Dir};
+use crate::engine::bitboard::{Bitboard, knight_attacks, king_attacks, pawn_attacks, rook_attacks, bishop_attacks, queen_attacks, between, line};
 use crate::engine::moves::{Move, MoveKind};
-use crate::engine::piece::{Color, PieceKind, Piece};
-use std::collections::HashSet;
-use std::iter::FromIterator;
-use std::fs::{read, read_to_string};
-use std::fmt::format;
+use crate::engine::move_list::{MoveList, StagedMoves};
+use crate::engine::piece::{Color, PieceKind};
+use crate::engine::error::ChessError;
+
+/// Moves a generation emits
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum MoveFilter{
+    All,
+    /// captures and promotions
+    Captures,
+    /// the other moves, castles included
+    Quiets,
+}
 
+/// King of the side to move, the pieces giving it check and the pieces pinned on it,
+/// computed once per position
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct CheckInfo{
+    king: Option<Case>,
+    checkers: Bitboard,
+    pinned: Bitboard,
+}
+impl CheckInfo{
+    /// Cases the piece on case, not the king, may move to without leaving its king in check
+    fn targets(&self, king: Case, case: Case) -> Bitboard{
+        // in check, the other pieces have to capture the checker or to block it
+        let targets = match self.checkers.first() {
+            Some(checker) => between(king, checker) | Bitboard::from_case(checker),
+            None => !Bitboard::EMPTY,
+        };
+        // a pinned piece stays on the line between its king and the pinner
+        if self.pinned.contains(case) {targets & line(king, case)} else {targets}
+    }
+}
 
 impl Board{
 
     /// get all legal moves for the current side
     pub fn get_moves(&self) -> Vec<Move>{
-        (0..64).map(|i| Case::new(i))
-            .filter_map(|p| self.get_moves_for_case(&p))
-            .flatten() // vector for psedo legal move
-            .filter(|mv| self.is_move_legal(mv)) // filter mate
-            .collect()
+        let mut moves = MoveList::new();
+        self.generate_moves(&mut moves);
+        moves.to_vec()
     }
 
-    /// Check the legality of a move (wether or not the king will be in check)
-    pub fn is_move_legal(&self, mv: &Move) -> bool{
-        let new_board = self.apply_move(mv);
-        let attacked_case: HashSet<Case> = HashSet::from_iter(new_board.get_attacked_case(&new_board.side).into_iter());
+    /// Add all the legal moves for the current side to the list
+    pub fn generate_moves(&self, moves: &mut MoveList){
+        self.generate(&self.check_info(), MoveFilter::All, moves);
+    }
 
-        // does king is in attacked cases
-        !attacked_case.contains(&new_board.get_kind_pos(&new_board.side.flip()))
+    /// Legal moves generated lazily, the captures and promotions first then the quiet moves
+    pub fn staged_moves(&self) -> StagedMoves<'_>{
+        StagedMoves::new(self)
     }
 
-    /// Return the list of case the given color currently attack
-    fn get_attacked_case(&self, color: &Color) -> Vec<Case>{
-        let mut new_board = self.clone();
-        new_board.side = *color;
-        (0..64).map(|i| Case::new(i))
-            .filter_map(|p| new_board.get_attack_move_for_case(&p))
-            .flatten()
-            .map(|mv| mv.to).collect()
+    /// Add the legal captures and promotions, quiet promotions included, to the list
+    pub fn generate_captures(&self, moves: &mut MoveList){
+        self.generate(&self.check_info(), MoveFilter::Captures, moves);
     }
 
-    /// Get pseudo legal moves for the piece on case
-    fn get_moves_for_case(&self, case: &Case) -> Option<Vec<Move>>{
-        if let Some(Piece{kind, color}) = self[case]{
-            if color != self.side{
-                return None
-            }
-            let mut moves = match kind {
-                PieceKind::King => self.castle_move(),
-                PieceKind::Pawn => self.pawn_quiet_moves(case),
-                _ => Vec::new()
-            };
-            moves.append(&mut self.get_attack_move_for_case(case).unwrap());
-            Some(moves)
-        }else{
-            None
-        }
+    /// Add the legal moves which are neither captures nor promotions to the list
+    pub fn generate_quiets(&self, moves: &mut MoveList){
+        self.generate(&self.check_info(), MoveFilter::Quiets, moves);
     }
 
-    /// Get potential attack for piece in the case (all but not pawn move or rock)
-    fn get_attack_move_for_case(&self, case: &Case) -> Option<Vec<Move>>{
-        if let Some(Piece{kind, color}) = self[case]{
-            if color != self.side{
-                return None
-            }
-            Some(match kind{
-                PieceKind::Queen => self.moves_for_dir(case,
-                                                       &[Dir::Up,
-                                                           Dir::UpLeft,
-                                                           Dir::UpRight,
-                                                           Dir::Right,
-                                                           Dir::Left,
-                                                           Dir::DownLeft,
-                                                           Dir::DownRight,
-                                                           Dir::Down], 8),
-                PieceKind::Rook => self.moves_for_dir(case,
-                                                      &[Dir::Up,
-                                                          Dir::Right,
-                                                          Dir::Left,
-                                                          Dir::Down], 8),
-                PieceKind::Bishop => self.moves_for_dir(case,
-                                                        &[Dir::UpLeft,
-                                                            Dir::UpRight,
-                                                            Dir::DownLeft,
-                                                            Dir::DownRight], 8),
-                PieceKind::Knight => self.moves_for_dir(case,
-                                                        &[
-                                                            Dir::Cav1,
-                                                            Dir::Cav2,
-                                                            Dir::Cav4,
-                                                            Dir::Cav5,
-                                                            Dir::Cav7,
-                                                            Dir::Cav8,
-                                                            Dir::Cav10,
-                                                            Dir::Cav11
-                                                        ], 1),
-                PieceKind::King => self.moves_for_dir(case,
-                                                      &[Dir::Up,
-                                                          Dir::UpLeft,
-                                                          Dir::UpRight,
-                                                          Dir::Right,
-                                                          Dir::Left,
-                                                          Dir::DownLeft,
-                                                          Dir::DownRight,
-                                                          Dir::Down], 1),
-                PieceKind::Pawn => self.pawn_attack_moves(case),
-            })
-        }else{
-            None
-        }
+    /// Add the legal quiet moves giving check, directly or by uncovering a slider, to the list
+    pub fn generate_quiet_checks(&self, moves: &mut MoveList){
+        let mut quiets = MoveList::new();
+        self.generate_quiets(&mut quiets);
+        moves.extend(quiets.into_iter().filter(|mv| self.gives_check(mv)));
     }
 
-    /// Get pseudo legal moves for a pawn situated in case
-    fn pawn_quiet_moves(&self, case: &Case) -> Vec<Move>{
-        let mut moves: Vec<Move> = Vec::new();
-        let (front, start_line, promotion_line) = match self.side {
-            Color::White => (Dir::Up, 1, 7),
-            Color::Black => (Dir::Down, 6, 0),
+    /// Does the legal move mv put the adversary king in check
+    pub fn gives_check(&self, mv: &Move) -> bool{
+        let king = match self.king_square(&self.side.flip()) {
+            Some(king) => king,
+            None => return false,
         };
-
-        // simple & double pawn push
-        let front_case = &case.get_neighbour(front, 1).unwrap();
-        if self[front_case].is_none(){
-            if front_case.get_line() == promotion_line {
-                moves.push(Move::new(case.clone(), front_case.clone(), MoveKind::QueenPromotion));
-                moves.push(Move::new(case.clone(), front_case.clone(), MoveKind::BishopPromotion));
-                moves.push(Move::new(case.clone(), front_case.clone(), MoveKind::RookPromotion));
-                moves.push(Move::new(case.clone(), front_case.clone(), MoveKind::KnightPromotion));
-            } else{
-                moves.push(Move::new(case.clone(), front_case.clone(), MoveKind::Quiet));
+        let from = Bitboard::from_case(mv.from());
+        let to = Bitboard::from_case(mv.to());
+        let mut occupied = (self.occupied() & !from) | to;
+        match mv.get_kind() {
+            MoveKind::KingCastle | MoveKind::QueenCastle => {
+                // only the rook can give check, the king does not uncover anything leaving its case
+                let (rook_from, rook_to) = Board::castle_rock_cases(mv);
+                occupied = (occupied & !Bitboard::from_case(rook_from)) | Bitboard::from_case(rook_to);
+                return rook_attacks(rook_to, occupied).contains(king)
             }
+            MoveKind::EnPassantCapture => occupied &= !Bitboard::from_case(Board::en_passant_taken_case(mv)),
+            _ => (),
+        }
+        let kind = match mv.get_promotion() {
+            Some(kind) => kind,
+            None => self[&mv.from()].expect("a piece to move").kind,
+        };
+        let direct = match kind {
+            PieceKind::Pawn => pawn_attacks(mv.to(), self.side),
+            PieceKind::Knight => knight_attacks(mv.to()),
+            PieceKind::Bishop => bishop_attacks(mv.to(), occupied),
+            PieceKind::Rook => rook_attacks(mv.to(), occupied),
+            PieceKind::Queen => queen_attacks(mv.to(), occupied),
+            PieceKind::King => Bitboard::EMPTY,
+        };
+        // the sliders behind the moved piece, not the moved piece itself which is still on from
+        let queens = self.pieces(PieceKind::Queen, self.side);
+        let discovered = ((rook_attacks(king, occupied) & (self.pieces(PieceKind::Rook, self.side) | queens))
+            | (bishop_attacks(king, occupied) & (self.pieces(PieceKind::Bishop, self.side) | queens))) & !from;
+        direct.contains(king) || !discovered.is_empty()
+    }
 
-            if case.get_line() == start_line && self[&case.get_neighbour(front, 2).unwrap()].is_none(){
-                moves.push(Move::new(case.clone(), case.get_neighbour(front, 2).unwrap(), MoveKind::DoublePawnPush));
-            }
+    pub(crate) fn check_info(&self) -> CheckInfo{
+        match self.king_square(&self.side) {
+            Some(king) => CheckInfo{
+                king: Some(king),
+                checkers: self.attackers_to(&king, &self.side.flip()),
+                pinned: self.pinned_on(&king),
+            },
+            None => CheckInfo{king: None, checkers: Bitboard::EMPTY, pinned: Bitboard::EMPTY},
         }
-        moves
     }
 
-    /// Return the cases controlled by a pawn
-    fn get_pawn_control_case(&self, case: &Case) -> Vec<Case>{
-        let mut cases: Vec<Case> = Vec::new();
-        let (front_left, front_right) = match self[case].unwrap().color {
-            Color::White => (Dir::UpLeft, Dir::UpRight),
-            Color::Black => (Dir::DownLeft, Dir::DownRight),
+    /// Add the legal moves selected by the filter to the list
+    pub(crate) fn generate(&self, info: &CheckInfo, filter: MoveFilter, moves: &mut MoveList){
+        let own = self.color_pieces(self.side);
+        let king = match info.king {
+            Some(king) => king,
+            None => {
+                // no king to protect, every pseudo legal move is legal
+                for case in own{
+                    self.add_moves_for_case(&case, !Bitboard::EMPTY, filter, moves);
+                }
+                if filter != MoveFilter::Captures{
+                    self.add_castle_moves(moves);
+                }
+                return
+            }
         };
-        if let Some(case) = case.get_neighbour(front_right, 1){
-            cases.push(case);
+        self.add_king_moves(&king, filter, moves);
+        if info.checkers.count() > 1{
+            return // double check, only the king can move
+        }
+
+        for case in own & !Bitboard::from_case(king){
+            self.add_moves_for_case(&case, info.targets(king, case), filter, moves);
         }
-        if let Some(case) = case.get_neighbour(front_left, 1){
-            cases.push(case);
+        if info.checkers.is_empty() && filter != MoveFilter::Captures{
+            self.add_castle_moves(moves);
         }
-        cases
     }
 
-    fn pawn_attack_moves(&self, case: &Case) -> Vec<Move>{
-        let mut moves: Vec<Move> = Vec::new();
-        let (front_left, front_right,  promotion_line) = match self.side {
-            Color::White => (Dir::UpLeft, Dir::UpRight, 7),
-            Color::Black => (Dir::DownLeft, Dir::DownRight, 0),
+    /// Find the legal move written in uci notation (ie: e2e4, e1g1, a7a8q)
+    pub fn parse_legal_move(&self, uci: &str) -> Result<Move, ChessError>{
+        let mv: Move = uci.parse()?;
+        let mut moves = MoveList::new();
+        self.generate_moves(&mut moves);
+        moves.iter()
+            .find(|legal| legal.from() == mv.from() && legal.to() == mv.to() && legal.get_promotion() == mv.get_promotion())
+            .copied()
+            .ok_or_else(|| ChessError::IllegalMove(uci.to_string()))
+    }
+
+    /// Check the legality of a move (wether or not the king will be in check)
+    pub fn is_move_legal(&self, mv: &Move) -> bool{
+        let mut board = *self;
+        board.is_king_safe_after(mv)
+    }
+
+    /// Play the move and take it back, return false if it leaves the king in check
+    fn is_king_safe_after(&mut self, mv: &Move) -> bool{
+        let side = self.side;
+        let undo = self.make_move(mv);
+        let safe = match self.king_square(&side) {
+            Some(king) => !self.is_attacked(&king, &self.side),
+            None => true,
         };
+        self.unmake_move(mv, &undo);
+        safe
+    }
 
-        for &side in [front_left, front_right].iter(){
-            if let Some(target) = case.get_neighbour(side, 1){
-                if self.is_adversary(&target, &self.side){
-                    if target.get_line() == promotion_line {
-                        moves.push(Move::new(case.clone(), target.clone(), MoveKind::QueenCapturePromotion));
-                        moves.push(Move::new(case.clone(), target.clone(), MoveKind::BishopCapturePromotion));
-                        moves.push(Move::new(case.clone(), target.clone(), MoveKind::RookCapturePromotion));
-                        moves.push(Move::new(case.clone(), target.clone(), MoveKind::KnightCapturePromotion));
-                    } else {
-                        moves.push(Move::new(case.clone(), target, MoveKind::SimpleCapture))
-                    }
-                }
-                if let Some(en_passant_target) = self.en_passant{
-                    if target == en_passant_target{
-                        moves.push(Move::new(case.clone(), target, MoveKind::EnPassantCapture))
-                    }
-                }
+    /// Is the king of the side to move currently attacked
+    pub fn in_check(&self) -> bool{
+        self.king_square(&self.side)
+            .is_some_and(|king| self.is_attacked(&king, &self.side.flip()))
+    }
+
+    /// Adversary pieces giving check to the king of the side to move
+    pub fn checkers(&self) -> Bitboard{
+        self.check_info().checkers
+    }
+
+    /// Pieces of the side to move pinned on their king: moving them off the line to the
+    /// attacking slider would leave the king in check
+    pub fn pinned_pieces(&self) -> Bitboard{
+        self.check_info().pinned
+    }
+
+    /// Is the side to move in check without legal move
+    pub fn is_checkmate(&self) -> bool{
+        self.in_check() && !self.has_legal_move()
+    }
+
+    /// Is the side to move not in check but without legal move
+    pub fn is_stalemate(&self) -> bool{
+        !self.in_check() && !self.has_legal_move()
+    }
+
+    /// Does the side to move have a legal move, stop at the first piece having one
+    fn has_legal_move(&self) -> bool{
+        let info = self.check_info();
+        let mut moves = MoveList::new();
+        let king = match info.king {
+            Some(king) => king,
+            None => {
+                self.generate(&info, MoveFilter::All, &mut moves);
+                return !moves.is_empty()
             }
+        };
+        self.add_king_moves(&king, MoveFilter::All, &mut moves);
+        if !moves.is_empty() || info.checkers.count() > 1{
+            return !moves.is_empty()
         }
-        moves
+        // no castle to try: a king which can castle can also step aside
+        (self.color_pieces(self.side) & !Bitboard::from_case(king)).any(|case| {
+            self.add_moves_for_case(&case, info.targets(king, case), MoveFilter::All, &mut moves);
+            !moves.is_empty()
+        })
     }
 
-    /// get castle move if possible
-    fn castle_move(&self) -> Vec<Move>{
-        let mut mv = Vec::new();
+    /// Is the case attacked by a piece of the given color
+    fn is_attacked(&self, case: &Case, color: &Color) -> bool{
+        !self.attackers_to(case, color).is_empty()
+    }
 
-        let (king_case, queen_case, mut can_king, mut can_queen) = match self.side {
-            Color::White => ([Case::new(4), Case::new(5), Case::new(6)],
-                             [Case::new(4), Case::new(3), Case::new(2), Case::new(1)],
-                             self.castle.white_king, self.castle.white_queen),
-            Color::Black => ([Case::new(60), Case::new(61), Case::new(62)],
-                             [Case::new(60), Case::new(59), Case::new(58), Case::new(57)],
-                             self.castle.black_king, self.castle.black_queen),
-        };
+    /// Return the pieces of the given color attacking the case
+    pub fn attackers_to(&self, case: &Case, color: &Color) -> Bitboard{
+        self.attackers_with(case, color, self.occupied())
+    }
 
-        if !can_king && ! can_queen{
-            return mv
-        }
+    /// Return the pieces of the given color attacking the case if the occupied cases were occupied
+    fn attackers_with(&self, case: &Case, color: &Color, occupied: Bitboard) -> Bitboard{
+        let queens = self.pieces(PieceKind::Queen, *color);
+        (pawn_attacks(*case, color.flip()) & self.pieces(PieceKind::Pawn, *color))
+            | (knight_attacks(*case) & self.pieces(PieceKind::Knight, *color))
+            | (king_attacks(*case) & self.pieces(PieceKind::King, *color))
+            | (rook_attacks(*case, occupied) & (self.pieces(PieceKind::Rook, *color) | queens))
+            | (bishop_attacks(*case, occupied) & (self.pieces(PieceKind::Bishop, *color) | queens))
+    }
 
-        match (self[&king_case[1]], self[&king_case[2]]){
-            (Some(p), _) => can_king = false,
-            (_, Some(p)) => can_king = false,
-            (_, _) => {}
+    /// Pieces of the side to move which are the only piece between king and an adversary slider
+    fn pinned_on(&self, king: &Case) -> Bitboard{
+        let adversary = self.side.flip();
+        let queens = self.pieces(PieceKind::Queen, adversary);
+        let snipers = (rook_attacks(*king, Bitboard::EMPTY) & (self.pieces(PieceKind::Rook, adversary) | queens))
+            | (bishop_attacks(*king, Bitboard::EMPTY) & (self.pieces(PieceKind::Bishop, adversary) | queens));
+        let occupied = self.occupied();
+        let mut pinned = Bitboard::EMPTY;
+        for sniper in snipers{
+            let blockers = between(*king, sniper) & occupied;
+            if blockers.count() == 1{
+                pinned |= blockers & self.color_pieces(self.side);
+            }
         }
-        match (self[&queen_case[1]], self[&queen_case[2]], self[&queen_case[3]]){
-            (Some(p), _, _) => can_queen = false,
-            (_, Some(p), _) => can_queen = false,
-            (_, _, Some(p)) => can_queen = false,
-            (_, _, _) => {}
+        pinned
+    }
+
+    /// Cases a piece other than a pawn may move to for the filter
+    fn filter_targets(&self, filter: MoveFilter) -> Bitboard{
+        match filter {
+            MoveFilter::All => !self.color_pieces(self.side),
+            MoveFilter::Captures => self.color_pieces(self.side.flip()),
+            MoveFilter::Quiets => !self.occupied(),
         }
+    }
 
-        if !can_king && ! can_queen{
-            return mv
+    /// Add the moves of the king to the cases not attacked. The king is removed from the
+    /// occupied cases so it can not hide behind itself from a slider
+    fn add_king_moves(&self, king: &Case, filter: MoveFilter, moves: &mut MoveList){
+        let adversary = self.side.flip();
+        let occupied = self.occupied() & !Bitboard::from_case(*king);
+        let adversaries = self.color_pieces(adversary);
+        for target in king_attacks(*king) & self.filter_targets(filter){
+            if self.attackers_with(&target, &adversary, occupied).is_empty(){
+                let kind = if adversaries.contains(target) {MoveKind::SimpleCapture} else {MoveKind::Quiet};
+                moves.push(Move::new(*king, target, kind));
+            }
         }
-        let color = self.side.flip();
-        let attacked_cases = self.get_controled_cases(&color);
-        if can_king && !attacked_cases.contains(&king_case[0])
-            && !attacked_cases.contains(&king_case[1])
-            && !attacked_cases.contains(&king_case[2]){
-            mv.push(Move::new(king_case[0], king_case[2], MoveKind::KingCastle))
-        };
-        if can_queen && !attacked_cases.contains(&queen_case[0])
-            && !attacked_cases.contains(&queen_case[1])
-            && !attacked_cases.contains(&queen_case[2]){
-            mv.push(Move::new(queen_case[0], queen_case[2], MoveKind::QueenCastle))
-        };
-        mv
     }
 
-    /// Get controled cases by piece of given color
-    /// Not the same than attacked_cases because a pawn can control a case without being able to
-    /// move to it (empty cases)
-    fn get_controled_cases(&self, color: &Color) -> HashSet<Case> {
-        let mut attacked_cases: HashSet<Case> = HashSet::from_iter(self.get_attacked_case(color).into_iter());
-        attacked_cases.extend(self.get_pawn_controled_cases(color).iter());
-        attacked_cases
+    /// Add the moves selected by the filter for the piece on case ending on one of the targets,
+    /// castle excepted
+    fn add_moves_for_case(&self, case: &Case, targets: Bitboard, filter: MoveFilter, moves: &mut MoveList){
+        let occupied = self.occupied();
+        let attacks = match self[case].map(|piece| piece.kind) {
+            Some(PieceKind::Pawn) => return self.add_pawn_moves(case, targets, filter, moves),
+            Some(PieceKind::Knight) => knight_attacks(*case),
+            Some(PieceKind::Bishop) => bishop_attacks(*case, occupied),
+            Some(PieceKind::Rook) => rook_attacks(*case, occupied),
+            Some(PieceKind::Queen) => queen_attacks(*case, occupied),
+            Some(PieceKind::King) => king_attacks(*case),
+            None => return,
+        } & self.filter_targets(filter) & targets;
+        let adversaries = self.color_pieces(self.side.flip());
+        for target in attacks{
+            let kind = if adversaries.contains(target) {MoveKind::SimpleCapture} else {MoveKind::Quiet};
+            moves.push(Move::new(*case, target, kind));
+        }
     }
 
-    /// Return cases controled by pawn of given color
-    fn get_pawn_controled_cases(&self, color: &Color) -> Vec<Case> {
-        (0..64)
-            .map(|i| Case::new(i))
-            .filter(|c| {
-                if let Some(Piece { kind: PieceKind::Pawn, color: col }) = self[c] { col == *color } else { false }
+    /// Add the moves selected by the filter for a pawn situated in case ending on one of the
+    /// targets. The promotions are not quiet moves
+    fn add_pawn_moves(&self, case: &Case, targets: Bitboard, filter: MoveFilter, moves: &mut MoveList){
+        let (front, start_line, promotion_line) = match self.side {
+            Color::White => (Dir::Up, 1, 7),
+            Color::Black => (Dir::Down, 6, 0),
+        };
+        let occupied = self.occupied();
+        let (captures, quiets) = (filter != MoveFilter::Quiets, filter != MoveFilter::Captures);
+
+        // simple & double pawn push, the double push may block a check the simple one does not
+        if let Some(front_case) = case.get_neighbour(front, 1).filter(|target| !occupied.contains(*target)){
+            if targets.contains(front_case){
+                if front_case.get_line() == promotion_line {
+                    if captures{
+                        Board::add_promotions(case, &front_case, false, moves);
+                    }
+                } else if quiets{
+                    moves.push(Move::new(*case, front_case, MoveKind::Quiet));
+                }
             }
-            )
-            .map(|c| self.get_pawn_control_case(&c))
-            .flatten()
-            .collect()
-    }
-
-    /// Get pseudo legal moves for piece given a list of dir and a max distance
-    fn moves_for_dir(&self,case: &Case, dirs: &[Dir], max: usize) -> Vec<Move>{
-        dirs.into_iter()
-            .map(|dir| self.all_moves_in_dir(case, dir, max).into_iter())
-            .flatten()
-            .collect()
-    }
-
-    /// Create all move in dir until, same color, capture or end of board
-    fn all_moves_in_dir(&self, from: &Case, dir: &Dir, max: usize) -> Vec<Move>{
-        let mut res: Vec<Move> = Vec::new();
-        let mut dist = 1;
-        while let Some(to) = from.get_neighbour(*dir, dist){
-            if let Some(Piece{color, kind}) = self[&to]{
-                if {color != self.side}{
-                    res.push(Move::new(from.clone(), to.clone(), MoveKind::SimpleCapture))
+            if quiets && case.get_line() == start_line{
+                let double_case = case.get_neighbour(front, 2).unwrap();
+                if !occupied.contains(double_case) && targets.contains(double_case){
+                    moves.push(Move::new(*case, double_case, MoveKind::DoublePawnPush));
                 }
-                break
+            }
+        }
+
+        // captures
+        if !captures{
+            return
+        }
+        let attacks = pawn_attacks(*case, self.side);
+        for target in attacks & self.color_pieces(self.side.flip()) & targets{
+            if target.get_line() == promotion_line {
+                Board::add_promotions(case, &target, true, moves);
             } else{
-                res.push(Move::new(from.clone(), to.clone(), MoveKind::Quiet))
+                moves.push(Move::new(*case, target, MoveKind::SimpleCapture));
             }
-            if dist == max{
-                break
+        }
+        if let Some(en_passant_target) = self.en_passant{
+            if attacks.contains(en_passant_target) && self.is_en_passant_legal(case, &en_passant_target){
+                moves.push(Move::new(*case, en_passant_target, MoveKind::EnPassantCapture));
             }
-            dist += 1
         }
-        res
     }
 
-    /// Is there an adversary on case for color
-    fn is_adversary(&self, case: &Case, color: &Color) -> bool{
-        if let Some(piece) = self[case] {
-            &piece.color != color
-        } else{
-            false
-        }
+    /// En passant removes two pawns from the same line, which may uncover the king: check the
+    /// attacks on the king with the occupation after the capture
+    fn is_en_passant_legal(&self, from: &Case, to: &Case) -> bool{
+        let king = match self.king_square(&self.side) {
+            Some(king) => king,
+            None => return true,
+        };
+        let taken = Bitboard::from_case(Case::new(from.get_line() * 8 + to.get_column()));
+        let occupied = (self.occupied() & !Bitboard::from_case(*from) & !taken) | Bitboard::from_case(*to);
+        (self.attackers_with(&king, &self.side.flip(), occupied) & !taken).is_empty()
     }
 
-    /// Get the king position for color
-    fn get_kind_pos(&self, color: &Color) -> Case{
-        for i in 0..64{
-            if let Some(Piece{color: col, kind: PieceKind::King}) = self[i]{
-                if col == *color{
-                    return Case::new(i)
-                }
-            }
-        }
-        panic!(format!("king not found on board \n{}", self.to_fen()))
+    /// Add the four promotions of a pawn move
+    fn add_promotions(from: &Case, to: &Case, capture: bool, moves: &mut MoveList){
+        let kinds = if capture{
+            [MoveKind::QueenCapturePromotion, MoveKind::BishopCapturePromotion, MoveKind::RookCapturePromotion, MoveKind::KnightCapturePromotion]
+        } else{
+            [MoveKind::QueenPromotion, MoveKind::BishopPromotion, MoveKind::RookPromotion, MoveKind::KnightPromotion]
+        };
+        moves.extend(kinds.iter().map(|kind| Move::new(*from, *to, *kind)));
     }
-}
-#[cfg(test)]
-mod tests{
-    use crate::engine::board::Board;
-    use crate::engine::moves::Move;
-    use test::Bencher;
 
-    fn perft(board: &Board, depth: u64) -> u64{
-        if depth == 0{
-            return 1
+    /// Add castle moves if possible: the cases between the king and the rook are empty and the
+    /// king does not cross an attacked case
+    fn add_castle_moves(&self, moves: &mut MoveList){
+        let (king, can_king, can_queen) = match self.side {
+            Color::White => (4, self.castle.white_king, self.castle.white_queen),
+            Color::Black => (60, self.castle.black_king, self.castle.black_queen),
+        };
+        let occupied = self.occupied();
+        let adversary = self.side.flip();
+        let is_free = |cases: &[usize]| cases.iter().all(|&case| !occupied.contains(Case::new(case)));
+        let is_safe = |cases: &[usize]| cases.iter().all(|&case| !self.is_attacked(&Case::new(case), &adversary));
+
+        if can_king && is_free(&[king + 1, king + 2]) && is_safe(&[king, king + 1, king + 2]){
+            moves.push(Move::new(Case::new(king), Case::new(king + 2), MoveKind::KingCastle))
         }
-        let mut sum = 0;
-        for mv in board.get_moves()
-            .into_iter(){
-            let nb = board.apply_move(&mv);
-            let perft = sub_perft(&nb, depth-1);
-            sum += perft;
-            println!("{}:  {}", mv, perft)
+        if can_queen && is_free(&[king - 1, king - 2, king - 3]) && is_safe(&[king, king - 1, king - 2]){
+            moves.push(Move::new(Case::new(king), Case::new(king - 2), MoveKind::QueenCastle))
         }
-        sum
     }
 
-    fn sub_perft(board: &Board, depth: u64) -> u64{
-        if depth == 0{
-            return 1
-        }
-        board.get_moves()
-            .into_iter()
-            .map(|m| board.apply_move(&m))
-            .map(|b| sub_perft(&b, depth-1))
-            .sum()
+    /// Case of the king of color, None if it has no king
+    pub fn king_square(&self, color: &Color) -> Option<Case>{
+        self.pieces(PieceKind::King, *color).first()
     }
+}
+#[cfg(test)]
+mod tests{
+    use crate::engine::board::{Board, Case};
+    use crate::engine::bitboard::Bitboard;
+    use crate::engine::piece::Color;
+    use crate::engine::perft::perft;
+    use crate::engine::move_list::MoveList;
+    use crate::engine::moves::MoveKind;
+    use crate::engine::error::ChessError;
 
     #[test]
     fn test_mov_gen_start_board(){
@@ -344,24 +425,11 @@ mod tests{
 
     #[test]
     fn test_mov_gen_2(){
-        let mut board = Board::new_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 0");
-        //board = board.apply_move(&"a1c1".parse().unwrap());
-        //board = board.apply_move(&"h3g2".parse().unwrap());
-        //assert_eq!(44, perft(&board, 1)); // after h3g2
-
+        let board = Board::new_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 0");
         assert_eq!(48, perft(&board, 1));
         assert_eq!(2039, perft(&board, 2));
         assert_eq!(97_862, perft(&board, 3));
         assert_eq!(4_085_603, perft(&board, 4));
-
-        let mut board = Board::new_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 0");
-        board = board.apply_move(&"a1c1".parse().unwrap());
-        //assert_eq!(1968, perft(&board, 2)) // after a1c1
-
-        let mut board = Board::new_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 0");
-        //board = board.apply_move(&"a1c1".parse().unwrap());
-        //board = board.apply_move(&"h3g2".parse().unwrap());
-        //assert_eq!(44, perft(&board, 1)); // after h3g2
     }
 
     #[test]
@@ -374,8 +442,9 @@ mod tests{
         assert_eq!(674_624, perft(&board, 5));
     }
 
-    #[bench]
-    fn test_mov_gen_3_bench(b: &mut Bencher){
+    #[test]
+    #[ignore] // long running, use `cargo test -- --ignored`
+    fn test_mov_gen_3_bench(){
         let board = Board::new_from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 0");
         assert_eq!(14, perft(&board, 1));
         assert_eq!(191, perft(&board, 2));
@@ -433,4 +502,135 @@ mod tests{
         let board = Board::new_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q2/PPPBBPpP/1R2K2R w Kkq - 0 2");
         assert_eq!(44, perft(&board, 1))
     }
+
+    #[test]
+    fn test_en_passant_discovered_check(){
+        // taking en passant would leave the white king on the rook line
+        let board = Board::new_from_fen("8/8/8/KPp4r/8/8/8/7k w - c6 0 1");
+        let moves: Vec<String> = board.get_moves().iter().map(|mv| mv.to_uci()).collect();
+        assert!(!moves.contains(&"b5c6".to_string()));
+        assert!(moves.contains(&"b5b6".to_string()));
+        // the double pushed pawn gives check, en passant takes the checker
+        let board = Board::new_from_fen("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1");
+        let moves: Vec<String> = board.get_moves().iter().map(|mv| mv.to_uci()).collect();
+        assert!(moves.contains(&"e4d3".to_string()));
+        assert!(!moves.contains(&"e4e3".to_string()));
+    }
+
+    #[test]
+    fn test_check_evasions(){
+        // double check from the knight and the rook, only the king moves
+        let board = Board::new_from_fen("r3k3/8/3N4/8/8/8/8/4R1K1 b - - 0 1");
+        let moves = board.get_moves();
+        assert!(!moves.is_empty());
+        assert!(moves.iter().all(|mv| mv.from().to_string() == "e8"));
+        // the king can not step back on the line of the checking rook
+        assert!(!moves.iter().any(|mv| mv.to().to_string() == "e7"));
+        // single check: capture the checker or block it
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/3N4/r3K2R w K - 0 1");
+        let mut moves: Vec<String> = board.get_moves().iter().map(|mv| mv.to_uci()).collect();
+        moves.sort();
+        assert_eq!(moves, vec!["d2b1", "e1e2", "e1f2"]);
+        assert!(board.get_moves().iter().all(|mv| board.is_move_legal(mv)));
+    }
+
+    #[test]
+    fn test_pinned_pieces(){
+        let board = Board::new_from_fen("4k3/4r3/8/8/8/8/4R3/4K3 b - - 0 1");
+        let rook_moves: Vec<String> = board.get_moves().iter()
+            .filter(|mv| mv.from().to_string() == "e7")
+            .map(|mv| mv.to().to_string())
+            .collect();
+        assert_eq!(rook_moves, vec!["e2", "e3", "e4", "e5", "e6"]);
+        // a pinned knight can not move at all
+        let board = Board::new_from_fen("4k3/8/2n5/1B6/8/8/8/4K3 b - - 0 1");
+        assert!(!board.get_moves().iter().any(|mv| mv.from().to_string() == "c6"));
+    }
+
+    /// Check the partial generators against get_moves on the position and the positions
+    /// reached from it up to depth
+    fn check_partial_generators(board: &mut Board, depth: u64){
+        let all = board.get_moves();
+        let (mut captures, mut quiets, mut checks) = (MoveList::new(), MoveList::new(), MoveList::new());
+        board.generate_captures(&mut captures);
+        board.generate_quiets(&mut quiets);
+        board.generate_quiet_checks(&mut checks);
+        let fen = board.to_fen();
+        assert_eq!(captures.len() + quiets.len(), all.len(), "{}", fen);
+        assert!(captures.iter().chain(quiets.iter()).all(|mv| all.contains(mv)), "{}", fen);
+        assert!(captures.iter().all(|mv| mv.is_capture() || mv.is_promotion()), "{}", fen);
+        assert!(quiets.iter().all(|mv| !mv.is_capture() && !mv.is_promotion()), "{}", fen);
+        for mv in all{
+            let undo = board.make_move(&mv);
+            let check = board.in_check();
+            if depth > 0{
+                check_partial_generators(board, depth - 1);
+            }
+            board.unmake_move(&mv, &undo);
+            assert_eq!(board.gives_check(&mv), check, "{} {}", fen, mv);
+            assert_eq!(checks.contains(&mv), check && quiets.contains(&mv), "{} {}", fen, mv);
+        }
+    }
+
+    #[test]
+    fn test_partial_generators(){
+        for fen in ["rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 0",
+                    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 0",
+                    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10"].iter(){
+            check_partial_generators(&mut Board::new_from_fen(fen), 1);
+        }
+        // a castle giving check with the rook
+        let board = Board::new_from_fen("5k2/8/8/8/8/8/8/4K2R w K - 0 1");
+        let mut checks = MoveList::new();
+        board.generate_quiet_checks(&mut checks);
+        assert!(checks.iter().any(|mv| mv.to_uci() == "e1g1"));
+    }
+
+    #[test]
+    fn test_position_queries(){
+        let case = |case: &str| Case::new_from_str(case);
+        // fool's mate
+        let board = Board::new_from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+        assert!(board.in_check());
+        assert!(board.is_checkmate());
+        assert!(!board.is_stalemate());
+        assert_eq!(board.checkers(), Bitboard::from_case(case("h4")));
+        assert_eq!(board.king_square(&Color::White), Some(case("e1")));
+        assert_eq!(board.attackers_to(&case("f3"), &Color::White), Bitboard::from_case(case("e2")) | Bitboard::from_case(case("g1")));
+
+        let board = Board::new_from_fen("7k/5Q2/8/8/8/8/8/K7 b - - 0 1");
+        assert!(!board.in_check());
+        assert!(board.is_stalemate());
+        assert!(!board.is_checkmate());
+
+        let board = Board::new_from_fen("4k3/4r3/8/1b6/8/8/4R3/4K3 w - - 0 1");
+        assert!(!board.is_checkmate() && !board.is_stalemate());
+        assert_eq!(board.pinned_pieces(), Bitboard::from_case(case("e2")));
+        let board = Board::new_from_fen("4k3/4r3/8/8/1b6/8/3NR3/4K3 w - - 0 1");
+        assert_eq!(board.pinned_pieces(), Bitboard::from_case(case("d2")) | Bitboard::from_case(case("e2")));
+        let board = Board::new_from_fen("4k3/4r3/8/8/1b6/8/8/4K3 w - - 0 1");
+        assert_eq!(board.checkers(), Bitboard::from_case(case("b4")) | Bitboard::from_case(case("e7")));
+        assert!(!board.is_checkmate());
+    }
+
+    #[test]
+    fn test_parse_legal_move(){
+        let board = Board::new_from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1");
+        for (uci, kind) in [("e1g1", MoveKind::KingCastle), ("e1c1", MoveKind::QueenCastle), ("e5d6", MoveKind::EnPassantCapture),
+                            ("b7b8q", MoveKind::QueenPromotion), ("b7a8n", MoveKind::KnightCapturePromotion), ("a1a8", MoveKind::SimpleCapture)]{
+            let mv = board.parse_legal_move(uci).unwrap();
+            assert_eq!((mv.to_uci().as_str(), mv.get_kind()), (uci, kind));
+        }
+        assert_eq!(board.parse_legal_move("e2e5"), Err(ChessError::IllegalMove("e2e5".to_string())));
+        assert_eq!(board.parse_legal_move("e5e7"), Err(ChessError::IllegalMove("e5e7".to_string())));
+        assert_eq!(board.parse_legal_move("b7b8"), Err(ChessError::IllegalMove("b7b8".to_string())));
+        assert_eq!(board.parse_legal_move("e8g8"), Err(ChessError::IllegalMove("e8g8".to_string())));
+        assert_eq!(board.parse_legal_move("e1"), Err(ChessError::MoveSyntax("e1".to_string())));
+        // the king can not castle through check
+        let board = Board::new_from_fen("r3k2r/8/8/8/8/8/5r2/R3K2R w KQkq - 0 1");
+        assert_eq!(board.parse_legal_move("e1g1"), Err(ChessError::IllegalMove("e1g1".to_string())));
+    }
 }
@@ -0,0 +1,180 @@
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use crate::engine::board::Board;
+use crate::engine::move_generation::{CheckInfo, MoveFilter};
+use crate::engine::moves::Move;
+
+/// Capacity of a move list, no position has more than 218 legal moves
+pub const MAX_MOVES: usize = 256;
+
+/// List of moves with a fixed capacity, kept on the stack so generating moves does not allocate
+#[derive(Copy, Clone)]
+pub struct MoveList{
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+impl MoveList{
+    pub fn new() -> Self{
+        MoveList{moves: [Move::EMPTY; MAX_MOVES], len: 0}
+    }
+
+    /// Add a move at the end of the list, panic if the list is full
+    pub fn push(&mut self, mv: Move){
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self){
+        self.len = 0;
+    }
+}
+impl Default for MoveList{
+    fn default() -> Self {
+        MoveList::new()
+    }
+}
+impl Deref for MoveList{
+    type Target = [Move];
+
+    fn deref(&self) -> &Self::Target {
+        &self.moves[..self.len]
+    }
+}
+impl DerefMut for MoveList{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.moves[..self.len]
+    }
+}
+impl fmt::Debug for MoveList{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+impl Extend<Move> for MoveList{
+    fn extend<T: IntoIterator<Item=Move>>(&mut self, iter: T) {
+        for mv in iter{
+            self.push(mv);
+        }
+    }
+}
+impl<'a> IntoIterator for &'a MoveList{
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+impl IntoIterator for MoveList{
+    type Item = Move;
+    type IntoIter = MoveListIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        MoveListIter{list: self, index: 0}
+    }
+}
+
+/// Iterator over the moves of an owned list
+pub struct MoveListIter{
+    list: MoveList,
+    index: usize,
+}
+impl Iterator for MoveListIter{
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mv = self.list.get(self.index).copied();
+        self.index += 1;
+        mv
+    }
+}
+
+/// Step of a staged generation
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Stage{
+    Captures,
+    Quiets,
+    Done,
+}
+
+/// Legal moves generated lazily: the captures and promotions first, then the quiet moves once
+/// all the captures have been consumed. A search cutting off on a capture never generates the
+/// quiet moves
+pub struct StagedMoves<'a>{
+    board: &'a Board,
+    info: CheckInfo,
+    stage: Stage,
+    moves: MoveList,
+    index: usize,
+}
+impl<'a> StagedMoves<'a>{
+    pub(crate) fn new(board: &'a Board) -> Self{
+        let info = board.check_info();
+        let mut moves = MoveList::new();
+        board.generate(&info, MoveFilter::Captures, &mut moves);
+        StagedMoves{board, info, stage: Stage::Captures, moves, index: 0}
+    }
+}
+impl Iterator for StagedMoves<'_>{
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index == self.moves.len(){
+            match self.stage {
+                Stage::Captures => {
+                    self.moves.clear();
+                    self.index = 0;
+                    self.board.generate(&self.info, MoveFilter::Quiets, &mut self.moves);
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets | Stage::Done => {
+                    self.stage = Stage::Done;
+                    return None
+                }
+            }
+        }
+        self.index += 1;
+        Some(self.moves[self.index - 1])
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::engine::board::{Board, Case};
+    use crate::engine::move_list::MoveList;
+    use crate::engine::moves::{Move, MoveKind};
+
+    #[test]
+    fn test_move_list(){
+        let mut list = MoveList::new();
+        assert!(list.is_empty());
+        let e2e4 = Move::new(Case::new_from_str("e2"), Case::new_from_str("e4"), MoveKind::DoublePawnPush);
+        let g1f3 = Move::new(Case::new_from_str("g1"), Case::new_from_str("f3"), MoveKind::Quiet);
+        list.push(e2e4);
+        list.push(g1f3);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[1], g1f3);
+        list.swap(0, 1);
+        assert_eq!(list.into_iter().collect::<Vec<Move>>(), vec![g1f3, e2e4]);
+        list.clear();
+        assert_eq!(list.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_staged_moves(){
+        for fen in ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 0",
+                    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                    "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1"].iter(){
+            let board = Board::new_from_fen(fen);
+            let staged: Vec<Move> = board.staged_moves().collect();
+            let mut all = board.get_moves();
+            assert_eq!(staged.len(), all.len(), "{}", fen);
+            all.retain(|mv| !staged.contains(mv));
+            assert!(all.is_empty(), "{}", fen);
+            // no quiet move before a capture or a promotion
+            let first_quiet = staged.iter().position(|mv| !mv.is_capture() && !mv.is_promotion()).unwrap_or(staged.len());
+            assert!(staged[first_quiet..].iter().all(|mv| !mv.is_capture() && !mv.is_promotion()), "{}", fen);
+        }
+    }
+}
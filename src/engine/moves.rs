@@ -1,185 +1,149 @@
-use crate::engine::board::{Case, ParseCaseError, Board};
-use crate::engine::moves::SimpleKind::{Quiet, DoublePawnPush, KingCastle, QueenCastle};
-use crate::engine::moves::CaptureKind::{Simple, EnPassant};
-use crate::engine::moves::PromotionKind::{Knight, Bishop, Rook, Queen};
-use crate::engine::moves::MoveKind::{SimpleCapture, KnightCapturePromotion, BishopCapturePromotion, RookCapturePromotion, QueenCapturePromotion, KnightPromotion, BishopPromotion, RookPromotion, QueenPromotion, EnPassantCapture};
+use crate::engine::board::{Case, Board};
+use crate::engine::error::ChessError;
+use std::convert::TryFrom;
 use std::fmt;
 use std::str::FromStr;
 use crate::engine::piece::{Piece, PieceKind};
 
+/// Kind of a move, the value is its 4 bits code in a packed move: the bit 2 is set for the
+/// captures and the bit 3 for the promotions
+/// (https://www.chessprogramming.org/Encoding_Moves)
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(u8)]
 pub enum MoveKind{
-    Quiet,
-    DoublePawnPush,
-    KingCastle,
-    QueenCastle,
-    SimpleCapture,
-    EnPassantCapture,
-    KnightPromotion,
-    BishopPromotion,
-    RookPromotion,
-    QueenPromotion,
-    KnightCapturePromotion,
-    BishopCapturePromotion,
-    RookCapturePromotion,
-    QueenCapturePromotion,
+    Quiet = 0,
+    DoublePawnPush = 1,
+    KingCastle = 2,
+    QueenCastle = 3,
+    SimpleCapture = 4,
+    EnPassantCapture = 5,
+    KnightPromotion = 8,
+    BishopPromotion = 9,
+    RookPromotion = 10,
+    QueenPromotion = 11,
+    KnightCapturePromotion = 12,
+    BishopCapturePromotion = 13,
+    RookCapturePromotion = 14,
+    QueenCapturePromotion = 15,
 }
+const CAPTURE_FLAG: u8 = 0b0100;
+const PROMOTION_FLAG: u8 = 0b1000;
 
-#[repr(C)]
+/// Error returned when a code is not the one of a move kind (6 and 7 are unused)
 #[derive(Debug, Copy, Clone)]
-enum PromotionKind{
-    Knight,
-    Bishop,
-    Rook,
-    Queen,
-}
-
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-enum SimpleKind{
-    Quiet,
-    DoublePawnPush,
-    KingCastle,
-    QueenCastle
-}
-
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
-enum CaptureKind{
-    Simple,
-    EnPassant,
-}
-
-#[repr(C)]
-#[derive(Copy, Clone)]
-union SpecialFlag{
-    simple: SimpleKind,
-    promotion: PromotionKind,
-    capture: CaptureKind,
-}
+pub struct MoveKindError;
+impl TryFrom<u8> for MoveKind{
+    type Error = MoveKindError;
 
-/// Special flags for move
-#[derive(Copy, Clone)]
-struct MoveFlags{
-    capture: bool,
-    promotion: bool,
-    kind: SpecialFlag,
-}
-impl From<MoveKind> for MoveFlags{
-    fn from(kind: MoveKind) -> Self {
-        match kind{
-            MoveKind::Quiet =>
-                MoveFlags{capture: false, promotion: false, kind:SpecialFlag{simple:Quiet}},
-            MoveKind::DoublePawnPush =>
-                MoveFlags{capture: false, promotion: false, kind:SpecialFlag{simple:DoublePawnPush}},
-            MoveKind::KingCastle =>
-                MoveFlags{capture: false, promotion: false, kind:SpecialFlag{simple:KingCastle}},
-            MoveKind::QueenCastle =>
-                MoveFlags{capture: false, promotion: false, kind:SpecialFlag{simple:QueenCastle}},
-            MoveKind::SimpleCapture =>
-                MoveFlags{capture: true, promotion: false, kind:SpecialFlag{capture:Simple}},
-            MoveKind::EnPassantCapture =>
-                MoveFlags{capture: true, promotion: false, kind:SpecialFlag{capture:EnPassant}},
-            MoveKind::KnightPromotion =>
-                MoveFlags{capture: false, promotion: true, kind:SpecialFlag{promotion:Knight}},
-            MoveKind::BishopPromotion =>
-                MoveFlags{capture: false, promotion: true, kind:SpecialFlag{promotion:Bishop}},
-            MoveKind::RookPromotion =>
-                MoveFlags{capture: false, promotion: true, kind:SpecialFlag{promotion:Rook}},
-            MoveKind::QueenPromotion =>
-                MoveFlags{capture: false, promotion: true, kind:SpecialFlag{promotion:Queen}},
-            MoveKind::KnightCapturePromotion =>
-                MoveFlags{capture: true, promotion: true, kind:SpecialFlag{promotion:Knight}},
-            MoveKind::BishopCapturePromotion =>
-                MoveFlags{capture: true, promotion: true, kind:SpecialFlag{promotion:Bishop}},
-            MoveKind::RookCapturePromotion =>
-                MoveFlags{capture: true, promotion: true, kind:SpecialFlag{promotion:Rook}},
-            MoveKind::QueenCapturePromotion =>
-                MoveFlags{capture: true, promotion: true, kind:SpecialFlag{promotion:Queen}},
-        }
-    }
-}
-impl Into<MoveKind> for MoveFlags{
-    fn into(self) -> MoveKind {
-        match self{
-            MoveFlags {capture: false, promotion: false, kind} =>
-            unsafe {
-                match kind.simple {
-                    Quiet => MoveKind::Quiet,
-                    DoublePawnPush => MoveKind::DoublePawnPush,
-                    KingCastle => MoveKind::KingCastle,
-                    QueenCastle => MoveKind::QueenCastle,
-                }
-            }
-            MoveFlags {capture: true, promotion: false, kind} =>
-            unsafe {
-                match kind.capture {
-                    Simple => SimpleCapture,
-                    EnPassant => EnPassantCapture,
-                }
-            }
-            MoveFlags {capture: false, promotion: true, kind} =>
-            unsafe {
-                match kind.promotion {
-                    Knight => KnightPromotion,
-                    Bishop => BishopPromotion,
-                    Rook => RookPromotion,
-                    Queen => QueenPromotion,
-                }
-            }
-            MoveFlags {capture: true, promotion: true, kind} =>
-            unsafe {
-                match kind.promotion{
-                    Knight => KnightCapturePromotion,
-                    Bishop => BishopCapturePromotion,
-                    Rook => RookCapturePromotion,
-                    Queen => QueenCapturePromotion
-                }
-            }
+    fn try_from(code: u8) -> Result<Self, Self::Error> {
+        match code {
+            0 => Ok(MoveKind::Quiet),
+            1 => Ok(MoveKind::DoublePawnPush),
+            2 => Ok(MoveKind::KingCastle),
+            3 => Ok(MoveKind::QueenCastle),
+            4 => Ok(MoveKind::SimpleCapture),
+            5 => Ok(MoveKind::EnPassantCapture),
+            8 => Ok(MoveKind::KnightPromotion),
+            9 => Ok(MoveKind::BishopPromotion),
+            10 => Ok(MoveKind::RookPromotion),
+            11 => Ok(MoveKind::QueenPromotion),
+            12 => Ok(MoveKind::KnightCapturePromotion),
+            13 => Ok(MoveKind::BishopCapturePromotion),
+            14 => Ok(MoveKind::RookCapturePromotion),
+            15 => Ok(MoveKind::QueenCapturePromotion),
+            _ => Err(MoveKindError),
         }
     }
 }
-
-#[derive(Debug, Copy, Clone)]
-pub struct MoveParseError;
-impl From<ParseCaseError> for MoveParseError{
-    fn from(_: ParseCaseError) -> Self {
-        MoveParseError
+impl From<MoveKind> for u8{
+    fn from(kind: MoveKind) -> Self {
+        kind as u8
     }
 }
 
-/// Represent a move for a chessboard
-pub struct Move{
-    pub from: Case,
-    pub to: Case,
-    flags: MoveFlags,
-}
+/// Represent a move for a chessboard, packed in 16 bits: the start case in the bits 0-5, the
+/// end case in the bits 6-11 and the kind code in the bits 12-15
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Move(u16);
 impl Move{
+    /// a1a1, never a legal move, used to fill the unused entries of the move lists
+    pub(crate) const EMPTY: Move = Move(0);
 
     /// Create a new move
     pub fn new(from: Case, to: Case, kind: MoveKind) -> Self{
-        Move{from, to, flags: kind.into()}
+        Move(from.index() as u16 | (to.index() as u16) << 6 | (kind as u16) << 12)
     }
 
-    /// Create a new move from an uci move text representation and a board.
+    /// Get back a move from its packed representation, None if the kind code is invalid
+    pub fn from_bits(bits: u16) -> Option<Self>{
+        MoveKind::try_from((bits >> 12) as u8).ok().map(|_| Move(bits))
+    }
+
+    /// Packed representation of the move
+    pub fn to_bits(self) -> u16{
+        self.0
+    }
+
+    pub fn from(&self) -> Case{
+        Case::new((self.0 & 0x3F) as usize)
+    }
+    pub fn to(&self) -> Case{
+        Case::new((self.0 >> 6 & 0x3F) as usize)
+    }
+
+    /// Create a new move from an uci move text representation and a board, panic if the move is invalid.
     /// The board is needed for setting the move metadata
     pub fn new_on_board(str: &str, board: &Board) -> Self{
-        let mut mv: Move = str.parse().unwrap();
+        Move::try_new_on_board(str, board).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Create a new move from an uci move text representation and a board.
+    /// Fail if there is no piece on the start case
+    pub fn try_new_on_board(str: &str, board: &Board) -> Result<Self, ChessError>{
+        let mut mv: Move = str.parse()?;
+        if board[&mv.from()].is_none(){
+            return Err(ChessError::IllegalMove(str.to_string()))
+        }
         mv.set_kind(mv.get_kind_on_board(board));
-        mv
+        Ok(mv)
     }
 
+    fn code(&self) -> u8{
+        (self.0 >> 12) as u8
+    }
     pub fn is_capture(&self) -> bool{
-        self.flags.capture
+        self.code() & CAPTURE_FLAG != 0
     }
     pub fn is_promotion(&self) -> bool{
-        self.flags.promotion
+        self.code() & PROMOTION_FLAG != 0
+    }
+    /// Get the kind of the piece the pawn is promoted to, if the move is a promotion
+    pub fn get_promotion(&self) -> Option<PieceKind>{
+        match self.get_kind() {
+            MoveKind::KnightPromotion | MoveKind::KnightCapturePromotion => Some(PieceKind::Knight),
+            MoveKind::BishopPromotion | MoveKind::BishopCapturePromotion => Some(PieceKind::Bishop),
+            MoveKind::RookPromotion | MoveKind::RookCapturePromotion => Some(PieceKind::Rook),
+            MoveKind::QueenPromotion | MoveKind::QueenCapturePromotion => Some(PieceKind::Queen),
+            _ => None,
+        }
     }
     pub fn get_kind(&self) -> MoveKind{
-        self.flags.into()
+        MoveKind::try_from(self.code()).expect("moves are built from a valid kind")
     }
     pub fn set_kind(&mut self, kind: MoveKind){
-        self.flags = kind.into();
+        self.0 = self.0 & 0x0FFF | (kind as u16) << 12;
+    }
+
+    /// Get the uci text representation of the move (ie: e2e4, a7a8q)
+    pub fn to_uci(self) -> String{
+        let promotion = match self.get_kind() {
+            MoveKind::KnightPromotion | MoveKind::KnightCapturePromotion => "n",
+            MoveKind::BishopPromotion | MoveKind::BishopCapturePromotion => "b",
+            MoveKind::RookPromotion | MoveKind::RookCapturePromotion => "r",
+            MoveKind::QueenPromotion | MoveKind::QueenCapturePromotion => "q",
+            _ => "",
+        };
+        format!("{}{}{}", self.from(), self.to(), promotion)
     }
 
 
@@ -216,22 +180,22 @@ impl Move{
 
     /// Is the move a capture ? // todo (check en passant)
     fn is_capture_on_board(&self, board: &Board) -> bool{
-        board[&self.to].is_some()
+        board[&self.to()].is_some()
     }
 
     /// Is the move a promotion ?
     fn is_promotion_on_board(&self, board: &Board) -> bool{
-        match board[&self.from] {
+        match board[&self.from()] {
             None => false,
-            Some(Piece{kind: PieceKind::Pawn, color:_}) => self.to.get_line() == 0 || self.to.get_line() == 7,
+            Some(Piece{kind: PieceKind::Pawn, color:_}) => self.to().get_line() == 0 || self.to().get_line() == 7,
             Some(Piece{kind:_, color:_}) => false,
         }
     }
 
     /// Is move a rock
     fn is_queen_rock_on_board(&self, board: &Board) -> bool{
-        if let Some(Piece{kind: PieceKind::King, color:_}) = board[&self.from]{
-            match (self.from.get_line(), self.from.get_column(), self.to.get_line(), self.to.get_column()) {
+        if let Some(Piece{kind: PieceKind::King, color:_}) = board[&self.from()]{
+            match (self.from().get_line(), self.from().get_column(), self.to().get_line(), self.to().get_column()) {
                 (0, 4, 0, 2) => true, // white queen rock
                 (7, 4, 7, 2) => true, // back queen rock
                 (_, _, _, _) => false
@@ -243,8 +207,8 @@ impl Move{
 
     /// Is move king rock
     fn is_king_rock_on_board(&self, board: &Board) -> bool{
-        if let Some(Piece{kind: PieceKind::King, color:_}) = board[&self.from]{
-            match (self.from.get_line(), self.from.get_column(), self.to.get_line(), self.to.get_column()) {
+        if let Some(Piece{kind: PieceKind::King, color:_}) = board[&self.from()]{
+            match (self.from().get_line(), self.from().get_column(), self.to().get_line(), self.to().get_column()) {
                 (0, 4, 0, 6) => true, // white king rock
                 (7, 4, 7, 6) => true, // back king rock
                 (_, _, _, _) => false
@@ -257,10 +221,10 @@ impl Move{
 
     /// Check if move is 'en passant'
     fn is_en_passant_on_board(&self, board: &Board) -> bool{
-        if let Some(Piece{kind: PieceKind::Pawn, color:_}) = board[&self.from]{
+        if let Some(Piece{kind: PieceKind::Pawn, color:_}) = board[&self.from()]{
             match board.en_passant{
                 None => false,
-                Some(case) => case == self.to
+                Some(case) => case == self.to()
             }
         }else {
             false
@@ -269,8 +233,8 @@ impl Move{
 
     /// Check if move is a double pawn push
     fn is_double_pawn_on_board(&self, board: &Board) -> bool{
-        if let Some(Piece{kind: PieceKind::Pawn, color:_}) = board[&self.from]{
-            match (self.from.get_line(), self.to.get_line()) {
+        if let Some(Piece{kind: PieceKind::Pawn, color:_}) = board[&self.from()]{
+            match (self.from().get_line(), self.to().get_line()) {
                 (1, 3) => true,
                 (6, 4) => true,
                 (_, _) => false
@@ -282,23 +246,32 @@ impl Move{
 }
 impl fmt::Display for Move{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}{}: {:?}", self.from, self.to, self.get_kind())
+        write!(f, "{}", self.to_uci())
+    }
+}
+impl fmt::Debug for Move{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:?}", self.to_uci(), self.get_kind())
     }
 }
 impl FromStr for Move{
-    type Err = MoveParseError;
+    type Err = ChessError;
 
     /// Create a move from a string. Be carefull to add the move kind with the board
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let from: Case = s[0..2].parse()?;
-        let to: Case = s[2..4].parse()?;
+        let error = || ChessError::MoveSyntax(s.to_string());
+        if s.len() != 4 && s.len() != 5{
+            return Err(error())
+        }
+        let from: Case = s.get(0..2).ok_or_else(error)?.parse().map_err(|_| error())?;
+        let to: Case = s.get(2..4).ok_or_else(error)?.parse().map_err(|_| error())?;
         if s.len() == 5{
             match s.chars().collect::<Vec<char>>()[4] {
                 'q' => Ok(Move::new(from, to, MoveKind::QueenPromotion)),
                 'n' => Ok(Move::new(from, to, MoveKind::KnightPromotion)),
                 'b' => Ok(Move::new(from, to, MoveKind::BishopPromotion)),
                 'r' => Ok(Move::new(from, to, MoveKind::RookPromotion)),
-                _ => Err(MoveParseError)
+                _ => Err(error())
             }
         } else{
             Ok(Move::new(from, to, MoveKind::Quiet))
@@ -308,9 +281,11 @@ impl FromStr for Move{
 
 #[cfg(test)]
 mod tests{
-    use crate::engine::moves::{Move, SimpleKind, MoveKind};
+    use std::convert::TryFrom;
+    use crate::engine::moves::{Move, MoveKind};
     use crate::engine::board::{Case, Board};
     use crate::engine::moves::MoveKind::DoublePawnPush;
+    use crate::engine::error::ChessError;
 
     #[test]
     fn test_print_move() {
@@ -318,6 +293,29 @@ mod tests{
         println!("{}", m1)
     }
 
+    #[test]
+    fn test_move_parse_error(){
+        for mv in ["", "e2", "e2e", "e2e4e5", "i2e4", "e2e9", "e7e8k", "é2e4"].iter(){
+            assert_eq!(mv.parse::<Move>(), Err(ChessError::MoveSyntax(mv.to_string())));
+        }
+    }
+
+    #[test]
+    fn test_try_new_on_board(){
+        let board = Board::new_board();
+        assert_eq!(Move::try_new_on_board("e2e4", &board).map(|mv| mv.get_kind()), Ok(MoveKind::DoublePawnPush));
+        assert_eq!(Move::try_new_on_board("e2e", &board), Err(ChessError::MoveSyntax("e2e".to_string())));
+        assert_eq!(Move::try_new_on_board("e4e5", &board), Err(ChessError::IllegalMove("e4e5".to_string())));
+    }
+
+    #[test]
+    fn test_move_to_uci(){
+        let board = Board::new_from_fen("2p1k2r/p2P2P1/8/8/4Pp2/8/1P6/R3K3 w - e3 0 1");
+        for mv in ["b2b4", "e1c1", "a1a7", "g7g8n", "d7c8q"].iter(){
+            assert_eq!(Move::new_on_board(mv, &board).to_uci(), *mv);
+        }
+    }
+
     #[test]
     fn test_move_creation_from_txt(){
         // see https://lichess.org/editor/2p1k2r/p2P2P1/8/8/4Pp2/8/1P6/R3K3_w_-_-_0_1
@@ -338,4 +336,23 @@ mod tests{
         assert_eq!(Move::new_on_board("d7c8r", &board).get_kind(), MoveKind::RookCapturePromotion);
         assert_eq!(Move::new_on_board("d7c8q", &board).get_kind(), MoveKind::QueenCapturePromotion);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_packed_move(){
+        assert_eq!(std::mem::size_of::<Move>(), 2);
+        let kinds = [MoveKind::Quiet, MoveKind::DoublePawnPush, MoveKind::KingCastle, MoveKind::QueenCastle,
+            MoveKind::SimpleCapture, MoveKind::EnPassantCapture, MoveKind::KnightPromotion, MoveKind::BishopPromotion,
+            MoveKind::RookPromotion, MoveKind::QueenPromotion, MoveKind::KnightCapturePromotion,
+            MoveKind::BishopCapturePromotion, MoveKind::RookCapturePromotion, MoveKind::QueenCapturePromotion];
+        for kind in kinds.iter(){
+            assert_eq!(MoveKind::try_from(u8::from(*kind)).unwrap(), *kind);
+            let mv = Move::new(Case::new_from_str("h7"), Case::new_from_str("a8"), *kind);
+            assert_eq!((mv.from(), mv.to(), mv.get_kind()), (Case::new_from_str("h7"), Case::new_from_str("a8"), *kind));
+            assert_eq!(Move::from_bits(mv.to_bits()), Some(mv));
+        }
+        assert!(MoveKind::try_from(6).is_err());
+        assert!(Move::from_bits(7 << 12).is_none());
+        assert!(Move::new(Case::new(0), Case::new(1), MoveKind::RookCapturePromotion).is_capture());
+        assert!(!Move::new(Case::new(0), Case::new(1), MoveKind::EnPassantCapture).is_promotion());
+    }
+}
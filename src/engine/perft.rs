@@ -0,0 +1,308 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use crate::engine::board::Board;
+use crate::engine::error::ChessError;
+use crate::engine::move_list::MoveList;
+use crate::engine::moves::Move;
+
+/// Largest size in MB of the table caching the counts of the subtrees
+pub const MAX_HASH_SIZE: usize = 1 << 16;
+
+/// How perft counts the nodes, the default plays every move
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct PerftMode{
+    /// Count the legal moves at depth 1 instead of playing them
+    pub bulk: bool,
+    /// Size in MB of the table caching the counts of the subtrees, no cache if 0
+    pub hash_size: usize,
+}
+
+/// Count of a subtree, stored with the full key and the depth to tell collisions apart
+#[derive(Debug, Copy, Clone, Default)]
+struct PerftEntry{
+    key: u64,
+    depth: u32,
+    nodes: u64,
+}
+
+/// Cache of the counts of the subtrees, indexed by Zobrist hash and depth. Transpositions are
+/// frequent in a perft tree, each one is only expanded once
+pub struct PerftTable{
+    entries: Vec<PerftEntry>,
+}
+impl PerftTable{
+    /// Create a table using size_mb megabytes, at most MAX_HASH_SIZE, an entry is always replaced
+    pub fn new(size_mb: usize) -> Self{
+        let len = (size_mb.min(MAX_HASH_SIZE) * 1024 * 1024 / std::mem::size_of::<PerftEntry>()).max(1);
+        PerftTable{entries: vec![PerftEntry::default(); len]}
+    }
+
+    fn index(&self, key: u64, depth: u32) -> usize{
+        // mix the depth in so the counts of the same position at several depths do not collide
+        ((key ^ (depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)) % self.entries.len() as u64) as usize
+    }
+
+    pub fn probe(&self, key: u64, depth: u32) -> Option<u64>{
+        let entry = &self.entries[self.index(key, depth)];
+        if entry.key == key && entry.depth == depth && entry.nodes != 0 {Some(entry.nodes)} else {None}
+    }
+
+    pub fn store(&mut self, key: u64, depth: u32, nodes: u64){
+        let index = self.index(key, depth);
+        self.entries[index] = PerftEntry{key, depth, nodes};
+    }
+}
+
+/// State of a perft run
+struct Perft{
+    bulk: bool,
+    table: Option<PerftTable>,
+}
+impl Perft{
+    fn new(mode: &PerftMode) -> Self{
+        let table = if mode.hash_size > 0 {Some(PerftTable::new(mode.hash_size))} else {None};
+        Perft{bulk: mode.bulk, table}
+    }
+
+    fn count(&mut self, board: &mut Board, depth: u32) -> u64{
+        if depth == 0{
+            return 1
+        }
+        if let Some(nodes) = self.table.as_ref().and_then(|table| table.probe(board.hash(), depth)){
+            return nodes
+        }
+        let mut moves = MoveList::new();
+        board.generate_moves(&mut moves);
+        if self.bulk && depth == 1{
+            return moves.len() as u64
+        }
+        let mut nodes = 0;
+        for mv in moves{
+            let undo = board.make_move(&mv);
+            nodes += self.count(board, depth - 1);
+            board.unmake_move(&mv, &undo);
+        }
+        if let Some(table) = self.table.as_mut(){
+            table.store(board.hash(), depth, nodes);
+        }
+        nodes
+    }
+}
+
+/// Count the leaf nodes of the tree of legal moves of depth, used to validate the move
+/// generation against known counts (https://www.chessprogramming.org/Perft)
+pub fn perft(board: &Board, depth: u32) -> u64{
+    perft_with(board, depth, &PerftMode::default())
+}
+
+/// Count the leaf nodes of the tree of depth, counting them as set by mode
+pub fn perft_with(board: &Board, depth: u32, mode: &PerftMode) -> u64{
+    let mut board = *board;
+    Perft::new(mode).count(&mut board, depth)
+}
+
+/// Perft of each legal move, to find which move differs from a reference count
+pub fn divide(board: &Board, depth: u32, mode: &PerftMode) -> Vec<(Move, u64)>{
+    let mut board = *board;
+    let mut perft = Perft::new(mode);
+    let mut moves = MoveList::new();
+    board.generate_moves(&mut moves);
+    moves.into_iter().map(|mv| {
+        let undo = board.make_move(&mv);
+        let nodes = perft.count(&mut board, depth.saturating_sub(1));
+        board.unmake_move(&mv, &undo);
+        (mv, nodes)
+    }).collect()
+}
+
+/// Error returned when a line of an EPD perft file can not be understood
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PerftParseError{
+    /// No position before the first `;`
+    MissingFen,
+    /// The position can not be loaded
+    InvalidFen(ChessError),
+    /// A count is not `D<depth> <nodes>`
+    InvalidCount(String),
+}
+impl fmt::Display for PerftParseError{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PerftParseError::MissingFen => write!(f, "missing fen"),
+            PerftParseError::InvalidFen(err) => write!(f, "invalid fen: {}", err),
+            PerftParseError::InvalidCount(count) => write!(f, "invalid count '{}'", count),
+        }
+    }
+}
+
+/// A position and its known perft counts, parsed from an EPD line such as
+/// `rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PerftPosition{
+    pub fen: String,
+    /// (depth, nodes) pairs
+    pub counts: Vec<(u32, u64)>,
+}
+impl FromStr for PerftPosition{
+    type Err = PerftParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split(';');
+        let fen = fields.next().map(str::trim).filter(|fen| !fen.is_empty()).ok_or(PerftParseError::MissingFen)?;
+        Board::try_from_fen(fen).and_then(|board| board.validate()).map_err(PerftParseError::InvalidFen)?;
+        let counts = fields.map(|field| {
+            let invalid = || PerftParseError::InvalidCount(field.trim().to_string());
+            let tokens: Vec<&str> = field.split_whitespace().collect();
+            match tokens.as_slice() {
+                [depth, nodes] if depth.starts_with('D') => Ok((
+                    depth[1..].parse().map_err(|_| invalid())?,
+                    nodes.parse().map_err(|_| invalid())?,
+                )),
+                _ => Err(invalid()),
+            }
+        }).collect::<Result<Vec<(u32, u64)>, PerftParseError>>()?;
+        Ok(PerftPosition{fen: fen.to_string(), counts})
+    }
+}
+
+/// First depth at which the count of a position differs from the expected one
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PerftFailure{
+    pub fen: String,
+    pub depth: u32,
+    pub expected: u64,
+    pub nodes: u64,
+}
+
+/// Check the counts of the positions up to max_depth, the positions are shared between `threads`
+/// threads and so is the hash size of the mode, each thread getting at least 1 MB.
+/// Return the number of nodes counted and the failures, in the order of the positions
+pub fn check_positions(positions: &[PerftPosition], max_depth: u32, threads: usize, mode: &PerftMode) -> (u64, Vec<PerftFailure>){
+    let threads = threads.clamp(1, positions.len().max(1));
+    let hash_size = if mode.hash_size > 0 {(mode.hash_size / threads).max(1)} else {0};
+    let mode = PerftMode{hash_size, ..*mode};
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..threads{
+            scope.spawn(|| {
+                let mut index = next.fetch_add(1, Ordering::Relaxed);
+                while let Some(position) = positions.get(index){
+                    let result = check_position(position, max_depth, &mode);
+                    results.lock().unwrap().push((index, result));
+                    index = next.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_unstable_by_key(|(index, _)| *index);
+    let nodes = results.iter().map(|(_, (nodes, _))| nodes).sum();
+    (nodes, results.into_iter().filter_map(|(_, (_, failure))| failure).collect())
+}
+
+/// Check the counts of a position by increasing depth, stop at the first one differing
+fn check_position(position: &PerftPosition, max_depth: u32, mode: &PerftMode) -> (u64, Option<PerftFailure>){
+    let mut board = Board::new_from_fen(&position.fen);
+    // the counts of the lower depths stay in the table for the next ones
+    let mut perft = Perft::new(mode);
+    let mut counts: Vec<(u32, u64)> = position.counts.iter().filter(|(depth, _)| *depth <= max_depth).copied().collect();
+    counts.sort_unstable();
+    let mut total = 0;
+    for (depth, expected) in counts{
+        let nodes = perft.count(&mut board, depth);
+        total += nodes;
+        if nodes != expected{
+            return (total, Some(PerftFailure{fen: position.fen.clone(), depth, expected, nodes}))
+        }
+    }
+    (total, None)
+}
+
+#[cfg(test)]
+mod tests{
+    use std::thread;
+    use crate::engine::board::Board;
+    use crate::engine::error::ChessError;
+    use crate::engine::perft::{perft, perft_with, divide, check_positions, PerftMode, PerftTable, PerftPosition, PerftParseError, PerftFailure};
+
+    /// Standard perft suite, with the counts up to depth 6
+    const SUITE: &str = include_str!("../../data/perftsuite.epd");
+
+    #[test]
+    fn test_divide(){
+        let board = Board::new_from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 0");
+        let divide = divide(&board, 3, &PerftMode::default());
+        assert_eq!(divide.len(), 14);
+        assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), perft(&board, 3));
+        let e2e4 = divide.iter().find(|(mv, _)| mv.to_uci() == "e2e4").unwrap();
+        assert_eq!(e2e4.1, perft(&board.apply_move(&e2e4.0), 2));
+        assert_eq!(perft(&board, 0), 1);
+    }
+
+    #[test]
+    fn test_parse_epd(){
+        let position: PerftPosition = "4k3/8/8/8/8/8/8/4K2R w K - 0 1 ;D1 15 ;D2 66 ".parse().unwrap();
+        assert_eq!(position.fen, "4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+        assert_eq!(position.counts, vec![(1, 15), (2, 66)]);
+        assert_eq!("".parse::<PerftPosition>(), Err(PerftParseError::MissingFen));
+        assert_eq!("8/8/8/8/8/8/8/8 w - - ;D1".parse::<PerftPosition>(), Err(PerftParseError::InvalidFen(ChessError::FenFieldCount(4))));
+        assert_eq!("4k3/8/8/8/8/8/8/4K3 w - - 0 1 ;D1".parse::<PerftPosition>(), Err(PerftParseError::InvalidCount("D1".to_string())));
+        assert_eq!("4k3/8/8/8/8/8/8/4K3 w - - 0 1 ;X1 3".parse::<PerftPosition>(), Err(PerftParseError::InvalidCount("X1 3".to_string())));
+    }
+
+    #[test]
+    fn test_perft_suite(){
+        let positions: Vec<PerftPosition> = SUITE.lines().map(|line| line.parse().unwrap()).collect();
+        assert_eq!(positions.len(), 126);
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+        let (nodes, failures) = check_positions(&positions, 4, threads, &PerftMode::default());
+        assert_eq!(failures, Vec::new());
+        assert!(nodes > 1_000_000);
+    }
+
+    #[test]
+    fn test_first_failure(){
+        let positions = vec![
+            "4k3/8/8/8/8/8/8/4K2R w K - 0 1 ;D1 15 ;D2 66".parse().unwrap(),
+            "4k3/8/8/8/8/8/8/R3K3 w Q - 0 1 ;D3 1288 ;D1 16 ;D2 70".parse().unwrap(),
+        ];
+        let (_, failures) = check_positions(&positions, 3, 2, &PerftMode::default());
+        assert_eq!(failures, vec![PerftFailure{fen: "4k3/8/8/8/8/8/8/R3K3 w Q - 0 1".to_string(), depth: 2, expected: 70, nodes: 71}]);
+        // more threads than positions and than megabytes of hash
+        let (_, cached) = check_positions(&positions, 3, 8, &PerftMode{bulk: true, hash_size: 1});
+        assert_eq!(cached, failures);
+    }
+
+    #[test]
+    fn test_perft_table(){
+        let mut table = PerftTable::new(1);
+        assert_eq!(table.probe(42, 3), None);
+        table.store(42, 3, 8902);
+        assert_eq!(table.probe(42, 3), Some(8902));
+        assert_eq!(table.probe(42, 4), None);
+        assert_eq!(table.probe(43, 3), None);
+    }
+
+    #[test]
+    fn test_fast_perft(){
+        let modes = [PerftMode{bulk: true, hash_size: 0}, PerftMode{bulk: false, hash_size: 1}, PerftMode{bulk: true, hash_size: 1}];
+        for fen in ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 0",
+                    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8"].iter(){
+            let board = Board::new_from_fen(fen);
+            let expected = perft(&board, 3);
+            for mode in modes.iter(){
+                assert_eq!(perft_with(&board, 3, mode), expected, "{} {:?}", fen, mode);
+            }
+        }
+        // the depth 6 of the long running test, fast enough with the cache
+        let board = Board::new_from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 0");
+        assert_eq!(perft_with(&board, 6, &PerftMode{bulk: true, hash_size: 16}), 11_030_083);
+        let divide = divide(&board, 4, &PerftMode{bulk: true, hash_size: 1});
+        assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 43_238);
+    }
+}
@@ -1,6 +1,7 @@
 use std::fmt;
 use std::fmt::{Formatter, Display};
 use std::str::FromStr;
+use crate::engine::error::ChessError;
 use crate::engine::piece::PieceKind::{King, Queen, Rook, Bishop, Knight, Pawn};
 use crate::engine::piece::Color::{White, Black};
 
@@ -26,6 +27,14 @@ impl Color{
             Black => White
         }
     }
+
+    /// Index of the color in tables: white 0, black 1
+    pub const fn index(&self) -> usize{
+        match self {
+            White => 0,
+            Black => 1,
+        }
+    }
 }
 
 /// Kind of a piece
@@ -38,6 +47,19 @@ pub enum PieceKind{
     Knight,
     Pawn,
 }
+impl PieceKind{
+    /// Index of the kind in tables, from king 0 to pawn 5
+    pub const fn index(&self) -> usize{
+        match self {
+            King => 0,
+            Queen => 1,
+            Rook => 2,
+            Bishop => 3,
+            Knight => 4,
+            Pawn => 5,
+        }
+    }
+}
 
 impl fmt::Display for PieceKind{
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -62,17 +84,13 @@ impl fmt::Display for Piece{
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self.color {
             White => {write!(f, "{}", self.kind.to_string().to_uppercase())}
-            Black => {write!(f, "{}", self.kind.to_string())}
+            Black => {write!(f, "{}", self.kind)}
         }
     }
 }
 
-/// Error returned when a piece can not be parse from a string
-#[derive(Debug, Copy, Clone)]
-pub struct PieceParseError;
-
 impl FromStr for Piece{
-    type Err = PieceParseError;
+    type Err = ChessError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
@@ -88,7 +106,7 @@ impl FromStr for Piece{
             "b" => Ok(Piece{kind:Bishop, color:Black}),
             "n" => Ok(Piece{kind:Knight, color:Black}),
             "p" => Ok(Piece{kind:Pawn, color:Black}),
-            _ => Err(PieceParseError)
+            _ => Err(ChessError::Piece(s.to_string()))
         }
     }
 }
@@ -0,0 +1,245 @@
+use crate::engine::board::{Board, Case};
+use crate::engine::error::ChessError;
+use crate::engine::moves::{Move, MoveKind};
+use crate::engine::piece::PieceKind;
+
+/// Uppercase letter of a piece kind in SAN
+fn piece_letter(kind: PieceKind) -> String{
+    kind.to_string().to_uppercase()
+}
+
+/// Piece kind of an uppercase SAN letter, `P` included
+fn piece_kind(letter: char) -> Option<PieceKind>{
+    match letter {
+        'K' => Some(PieceKind::King),
+        'Q' => Some(PieceKind::Queen),
+        'R' => Some(PieceKind::Rook),
+        'B' => Some(PieceKind::Bishop),
+        'N' => Some(PieceKind::Knight),
+        'P' => Some(PieceKind::Pawn),
+        _ => None,
+    }
+}
+
+fn file(letter: char) -> Option<usize>{
+    ('a'..='h').position(|file| file == letter)
+}
+
+fn rank(digit: char) -> Option<usize>{
+    ('1'..='8').position(|rank| rank == digit)
+}
+
+impl Board{
+    /// Write a legal move in standard algebraic notation (ie: e4, Nbd7, exd6, O-O, e8=Q+, Qh4#)
+    pub fn move_to_san(&self, mv: &Move) -> String{
+        let mut san = match mv.get_kind() {
+            MoveKind::KingCastle => "O-O".to_string(),
+            MoveKind::QueenCastle => "O-O-O".to_string(),
+            _ => {
+                let kind = self[&mv.from()].map_or(PieceKind::Pawn, |piece| piece.kind);
+                let mut san = String::new();
+                if kind != PieceKind::Pawn{
+                    san.push_str(&piece_letter(kind));
+                    san.push_str(&self.disambiguation(mv, kind));
+                } else if mv.is_capture(){
+                    san.push_str(&mv.from().to_string()[..1]);
+                }
+                if mv.is_capture(){
+                    san.push('x');
+                }
+                san.push_str(&mv.to().to_string());
+                if let Some(promotion) = mv.get_promotion(){
+                    san.push('=');
+                    san.push_str(&piece_letter(promotion));
+                }
+                san
+            }
+        };
+        let board = self.apply_move(mv);
+        if board.is_checkmate(){
+            san.push('#');
+        } else if board.in_check(){
+            san.push('+');
+        }
+        san
+    }
+
+    /// Start case part needed to tell the move from the moves of the other pieces of the same
+    /// kind going to the same case: nothing, the file, the rank or both
+    fn disambiguation(&self, mv: &Move, kind: PieceKind) -> String{
+        let others: Vec<Case> = self.get_moves().iter()
+            .filter(|other| other.to() == mv.to() && other.from() != mv.from())
+            .filter(|other| self[&other.from()].is_some_and(|piece| piece.kind == kind))
+            .map(|other| other.from())
+            .collect();
+        let from = mv.from().to_string();
+        if others.is_empty(){
+            String::new()
+        } else if others.iter().all(|other| other.get_column() != mv.from().get_column()){
+            from[..1].to_string()
+        } else if others.iter().all(|other| other.get_line() != mv.from().get_line()){
+            from[1..].to_string()
+        } else {
+            from
+        }
+    }
+
+    /// Find the legal move written in standard algebraic notation. The parsing is forgiving:
+    /// check marks, annotations, `x`, `-`, `=` and `e.p.` are optional, castles may be written
+    /// with zeros, the start case may be given in full (ie: Ng1f3, e2e4) and a promotion
+    /// without piece is a queen promotion
+    pub fn parse_san(&self, san: &str) -> Result<Move, ChessError>{
+        let syntax = || ChessError::MoveSyntax(san.to_string());
+        let text = san.trim();
+        let text = text.strip_suffix("e.p.").unwrap_or(text);
+        let mut chars: Vec<char> = text.chars()
+            .filter(|car| !matches!(car, '+' | '#' | '!' | '?' | 'x' | ':' | '-' | '=' | '(' | ')' | ' '))
+            .collect();
+
+        let castle = match chars.iter().collect::<String>().as_str() {
+            "OO" | "oo" | "00" => Some(MoveKind::KingCastle),
+            "OOO" | "ooo" | "000" => Some(MoveKind::QueenCastle),
+            _ => None,
+        };
+        if let Some(castle) = castle{
+            return self.get_moves().into_iter().find(|mv| mv.get_kind() == castle)
+                .ok_or_else(|| ChessError::IllegalMove(san.to_string()))
+        }
+
+        // promotion piece after the target case
+        let mut promotion = None;
+        if chars.len() > 2 && chars[chars.len() - 2].is_ascii_digit(){
+            let letter = chars.pop().ok_or_else(syntax)?;
+            match piece_kind(letter.to_ascii_uppercase()) {
+                Some(PieceKind::King) | Some(PieceKind::Pawn) | None => return Err(syntax()),
+                kind => promotion = kind,
+            }
+        }
+
+        // piece letter, a lowercase b is the file of a pawn
+        let kind = match chars.first() {
+            Some(&letter) if letter != 'b' && piece_kind(letter.to_ascii_uppercase()).is_some() && chars.len() > 2 => {
+                chars.remove(0);
+                piece_kind(letter.to_ascii_uppercase()).ok_or_else(syntax)?
+            }
+            _ => PieceKind::Pawn,
+        };
+
+        if chars.len() < 2 || chars.len() > 4{
+            return Err(syntax())
+        }
+        let (start, target) = chars.split_at(chars.len() - 2);
+        let to = match (file(target[0]), rank(target[1])) {
+            (Some(col), Some(line)) => Case::new(col + 8 * line),
+            _ => return Err(syntax()),
+        };
+        let mut from_file = None;
+        let mut from_rank = None;
+        for &car in start{
+            match (file(car), rank(car)) {
+                (Some(col), _) if from_file.is_none() && from_rank.is_none() => from_file = Some(col),
+                (_, Some(line)) if from_rank.is_none() => from_rank = Some(line),
+                _ => return Err(syntax()),
+            }
+        }
+
+        let candidates: Vec<Move> = self.get_moves().into_iter()
+            .filter(|mv| mv.to() == to && self[&mv.from()].is_some_and(|piece| piece.kind == kind))
+            .filter(|mv| match from_file {
+                Some(col) => mv.from().get_column() == col,
+                None => true,
+            })
+            .filter(|mv| match from_rank {
+                Some(line) => mv.from().get_line() == line,
+                None => true,
+            })
+            .filter(|mv| match mv.get_promotion() {
+                None => promotion.is_none(),
+                Some(piece) => piece == promotion.unwrap_or(PieceKind::Queen),
+            })
+            .collect();
+        match candidates.as_slice() {
+            [mv] => Ok(*mv),
+            [] => Err(ChessError::IllegalMove(san.to_string())),
+            _ => Err(ChessError::AmbiguousMove(san.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::engine::board::Board;
+    use crate::engine::error::ChessError;
+    use crate::engine::moves::Move;
+
+    fn san(fen: &str, uci: &str) -> String{
+        let board = Board::new_from_fen(fen);
+        board.move_to_san(&board.parse_legal_move(uci).unwrap())
+    }
+
+    #[test]
+    fn test_move_to_san(){
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(san(start, "e2e4"), "e4");
+        assert_eq!(san(start, "g1f3"), "Nf3");
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        assert_eq!(san(kiwipete, "e1g1"), "O-O");
+        assert_eq!(san(kiwipete, "e1c1"), "O-O-O");
+        assert_eq!(san(kiwipete, "d5e6"), "dxe6");
+        assert_eq!(san(kiwipete, "e5f7"), "Nxf7");
+        assert_eq!(san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+        // disambiguation by file, by rank and by both
+        let queens = "8/2k5/8/8/4Q2Q/8/8/K6Q w - - 0 1";
+        assert_eq!(san(queens, "e4e1"), "Qee1");
+        assert_eq!(san(queens, "h1e1"), "Q1e1");
+        assert_eq!(san(queens, "h4e1"), "Qh4e1");
+        assert_eq!(san("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", "a1d1"), "Rad1");
+        // promotions, checks and mate
+        let promotion = "r6k/1P6/8/8/8/8/8/7K w - - 0 1";
+        assert_eq!(san(promotion, "b7b8q"), "b8=Q+");
+        assert_eq!(san(promotion, "b7a8r"), "bxa8=R+");
+        assert_eq!(san(promotion, "b7b8n"), "b8=N");
+        assert_eq!(san("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2", "d8h4"), "Qh4#");
+    }
+
+    #[test]
+    fn test_parse_san(){
+        let board = Board::new_board();
+        let uci = |san: &str| board.parse_san(san).map(Move::to_uci);
+        for san in ["Nf3", "Ng1f3", "Ng1-f3", "nf3", "Nf3!?", "N1f3"].iter(){
+            assert_eq!(uci(san), Ok("g1f3".to_string()), "{}", san);
+        }
+        for san in ["e4", "e2e4", "e2-e4", "Pe4", " e4 "].iter(){
+            assert_eq!(uci(san), Ok("e2e4".to_string()), "{}", san);
+        }
+        assert_eq!(uci("Nd2"), Err(ChessError::IllegalMove("Nd2".to_string())));
+        assert_eq!(uci("O-O"), Err(ChessError::IllegalMove("O-O".to_string())));
+        for san in ["", "e", "e9", "Zf3", "Nf3f3f3", "e8K"].iter(){
+            assert_eq!(uci(san), Err(ChessError::MoveSyntax(san.to_string())), "{}", san);
+        }
+
+        let board = Board::new_from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1");
+        let uci = |san: &str| board.parse_san(san).map(Move::to_uci);
+        for (san, expected) in [("O-O", "e1g1"), ("0-0+", "e1g1"), ("O-O-O", "e1c1"), ("exd6 e.p.", "e5d6"), ("ed6", "e5d6"),
+                                ("b8=Q", "b7b8q"), ("b8Q+", "b7b8q"), ("b8", "b7b8q"), ("b8=n", "b7b8n"), ("bxa8=R", "b7a8r"),
+                                ("Rxa8+", "a1a8")]{
+            assert_eq!(uci(san), Ok(expected.to_string()), "{}", san);
+        }
+
+        let board = Board::new_from_fen("8/2k5/8/8/4Q2Q/8/8/K6Q w - - 0 1");
+        assert_eq!(board.parse_san("Qe1"), Err(ChessError::AmbiguousMove("Qe1".to_string())));
+        assert_eq!(board.parse_san("Qhe1"), Err(ChessError::AmbiguousMove("Qhe1".to_string())));
+        assert_eq!(board.parse_san("Qh4e1").map(Move::to_uci), Ok("h4e1".to_string()));
+    }
+
+    #[test]
+    fn test_san_round_trip(){
+        for line in include_str!("../../data/perftsuite.epd").lines(){
+            let board = Board::new_from_fen(line.split(';').next().unwrap().trim());
+            for mv in board.get_moves(){
+                let san = board.move_to_san(&mv);
+                assert_eq!(board.parse_san(&san), Ok(mv), "{} in {}", san, board.to_fen());
+            }
+        }
+    }
+}
@@ -0,0 +1,605 @@
+use crate::engine::board::Board;
+use crate::engine::moves::Move;
+use crate::engine::move_list::MoveList;
+use crate::engine::eval::{evaluate, piece_value};
+use crate::engine::time_manager::TimeManager;
+use crate::engine::transposition::{TranspositionTable, Bound};
+use std::time::{Duration, Instant};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Score of a checkmate, the distance to mate in ply is subtracted from it
+pub const MATE_SCORE: i32 = 100_000;
+/// Bound larger than any reachable score
+pub const INFINITY: i32 = 1_000_000;
+
+/// Maximum depth of a search, in ply
+pub const MAX_DEPTH: u32 = 64;
+/// Number of nodes searched between two checks of the clock
+const CLOCK_CHECK_INTERVAL: u64 = 1024;
+/// The root move being searched is only reported after this delay
+const CURRENT_MOVE_INFO_DELAY: Duration = Duration::from_secs(1);
+/// Size of the transposition table in MB, when not given
+pub const DEFAULT_HASH_SIZE: usize = 16;
+
+/// Limits of a search, as given by the uci `go` command.
+/// A limit set to None is not applied.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct SearchLimits{
+    /// Remaining time for white
+    pub wtime: Option<Duration>,
+    /// Remaining time for black
+    pub btime: Option<Duration>,
+    /// White increment per move
+    pub winc: Option<Duration>,
+    /// Black increment per move
+    pub binc: Option<Duration>,
+    /// Number of moves before the next time control
+    pub movestogo: Option<u32>,
+    /// Maximum depth to search, in ply
+    pub depth: Option<u32>,
+    /// Maximum number of nodes to search
+    pub nodes: Option<u64>,
+    /// Search a mate in this number of moves
+    pub mate: Option<u32>,
+    /// Exact time to search
+    pub movetime: Option<Duration>,
+    /// Search until stopped
+    pub infinite: bool,
+    /// Search in ponder mode, the deadlines are only applied after ponderhit
+    pub ponder: bool,
+    /// Only search these root moves, all the legal moves when empty
+    pub searchmoves: Vec<Move>,
+}
+impl SearchLimits{
+    /// Maximum depth allowed by the limits
+    pub fn max_depth(&self) -> u32{
+        let mut max_depth = self.depth.unwrap_or(MAX_DEPTH);
+        if let Some(plies) = self.mate_plies(){
+            max_depth = max_depth.min(plies);
+        }
+        max_depth.clamp(1, MAX_DEPTH)
+    }
+
+    /// Number of plies of the searched mate, if any
+    fn mate_plies(&self) -> Option<u32>{
+        self.mate.map(|mate| mate.saturating_mul(2).saturating_sub(1))
+    }
+}
+
+/// Result of a search
+pub struct SearchResult{
+    /// Best move found, None if the side to move has no legal move
+    pub best_move: Option<Move>,
+    /// Score of the best move from the point of view of the side to move
+    pub score: i32,
+    /// Depth of the last completed iteration
+    pub depth: u32,
+    /// Principal variation, starting with the best move
+    pub pv: Vec<Move>,
+}
+
+/// Progress of a running search
+pub enum SearchInfo<'a>{
+    /// An iteration of the iterative deepening is complete
+    Iteration{
+        depth: u32,
+        /// Deepest ply reached
+        seldepth: u32,
+        score: i32,
+        nodes: u64,
+        time: Duration,
+        /// Usage of the transposition table, in permill
+        hashfull: u32,
+        pv: &'a [Move],
+    },
+    /// A root move starts to be searched
+    CurrentMove{
+        depth: u32,
+        mv: Move,
+        /// Index of the move in the root moves, starting at 1
+        number: usize,
+    },
+}
+
+/// Return the number of moves before mate if the score is a mate score,
+/// negative when the side to move is mated
+pub fn mate_in(score: i32) -> Option<i32>{
+    if score >= MATE_SCORE - MAX_DEPTH as i32{
+        Some((MATE_SCORE - score + 1) / 2)
+    } else if score <= -MATE_SCORE + MAX_DEPTH as i32{
+        Some(-(MATE_SCORE + score) / 2)
+    } else{
+        None
+    }
+}
+
+/// Mate scores are stored relative to the position in the transposition table,
+/// so they stay valid when the position is reached at another ply
+fn score_to_table(score: i32, ply: i32) -> i32{
+    if mate_in(score).is_some(){
+        score + score.signum() * ply
+    } else{
+        score
+    }
+}
+
+/// Inverse of score_to_table
+fn score_from_table(score: i32, ply: i32) -> i32{
+    if mate_in(score).is_some(){
+        score - score.signum() * ply
+    } else{
+        score
+    }
+}
+
+/// Flags used to control a search running on another thread
+#[derive(Debug, Default)]
+pub struct SearchSignals{
+    /// Abort the search as soon as possible
+    pub stop: AtomicBool,
+    /// The search is pondering, the deadlines start on ponderhit, when the flag is cleared
+    pub ponder: AtomicBool,
+}
+
+/// Callback receiving the progress of the search
+pub type InfoCallback = Box<dyn FnMut(SearchInfo) + Send>;
+
+/// Negamax alpha-beta search, with iterative deepening
+pub struct Search{
+    nodes: u64,
+    /// Deepest ply reached during the current iteration
+    seldepth: u32,
+    /// Set when the search has to be aborted
+    stopped: bool,
+    node_limit: Option<u64>,
+    /// Deadlines of the current search
+    time: TimeManager,
+    /// Results of the previous searches, kept between moves
+    table: TranspositionTable,
+    signals: Arc<SearchSignals>,
+    on_info: Option<InfoCallback>,
+}
+impl Search{
+    pub fn new() -> Self{
+        Search::with_hash_size(DEFAULT_HASH_SIZE)
+    }
+
+    /// Create a search with a transposition table of size_mb MB
+    pub fn with_hash_size(size_mb: usize) -> Self{
+        Search{
+            nodes: 0,
+            seldepth: 0,
+            stopped: false,
+            node_limit: None,
+            time: TimeManager::infinite(),
+            table: TranspositionTable::new(size_mb),
+            signals: Arc::new(SearchSignals::default()),
+            on_info: None
+        }
+    }
+
+    /// Resize the transposition table, its content is lost
+    pub fn resize_hash(&mut self, size_mb: usize){
+        self.table.resize(size_mb);
+    }
+
+    /// Forget the results of the previous searches
+    pub fn clear_hash(&mut self){
+        self.table.clear();
+    }
+
+    /// Get the signals controlling this search
+    pub fn signals(&self) -> Arc<SearchSignals>{
+        Arc::clone(&self.signals)
+    }
+
+    /// Set the callback receiving the progress of the search
+    pub fn set_info_callback(&mut self, callback: InfoCallback){
+        self.on_info = Some(callback);
+    }
+
+    /// Search the best move for the side to move within the limits
+    pub fn search(&mut self, board: &Board, limits: &SearchLimits, time: TimeManager) -> SearchResult{
+        let start = Instant::now();
+        self.time = time;
+        self.nodes = 0;
+        self.stopped = false;
+        self.node_limit = limits.nodes;
+        self.table.new_search();
+
+        let mut moves = board.get_moves();
+        if moves.is_empty(){
+            return SearchResult{best_move: None, score: Search::terminal_score(board, 0), depth: 0, pv: Vec::new()}
+        }
+        // the searchmoves are not checked by the parser, ignore them if none is legal
+        let searched: Vec<Move> = moves.iter()
+            .filter(|mv| limits.searchmoves.iter().any(|searched| searched.to_uci() == mv.to_uci()))
+            .copied().collect();
+        if !searched.is_empty(){
+            moves = searched;
+        }
+        order_moves(board, &mut moves);
+        // the moves are played and taken back on this board during the search
+        let mut board = *board;
+
+        let mut result = SearchResult{best_move: Some(moves[0]), score: 0, depth: 0, pv: vec![moves[0]]};
+        for depth in 1..=limits.max_depth(){
+            self.seldepth = 0;
+            let (best_index, score, pv) = self.search_root(&mut board, &moves, depth, start);
+            if self.stopped{
+                break // the iteration is incomplete, keep the previous one
+            }
+            result = SearchResult{best_move: Some(moves[best_index]), score, depth, pv};
+            // search the best move first on the next iteration
+            moves[..=best_index].rotate_right(1);
+            let (seldepth, nodes, hashfull) = (self.seldepth, self.nodes, self.table.hashfull());
+            self.info(SearchInfo::Iteration{depth, seldepth, score, nodes, time: start.elapsed(), hashfull, pv: &result.pv});
+
+            if let Some(plies) = limits.mate_plies(){
+                if score >= MATE_SCORE - plies.min(MAX_DEPTH) as i32{
+                    break
+                }
+            }
+            self.check_ponderhit();
+            if self.time.soft_limit_reached(){
+                break
+            }
+        }
+        result
+    }
+
+    /// Search all root moves at depth, return the index of the best one, its score and the
+    /// principal variation
+    fn search_root(&mut self, board: &mut Board, moves: &[Move], depth: u32, start: Instant) -> (usize, i32, Vec<Move>){
+        self.nodes += 1;
+        let mut alpha = -INFINITY;
+        let mut best_index = 0;
+        let mut pv = Vec::new();
+        let mut child_pv = Vec::new();
+        for (index, mv) in moves.iter().enumerate(){
+            if start.elapsed() >= CURRENT_MOVE_INFO_DELAY{
+                self.info(SearchInfo::CurrentMove{depth, mv: *mv, number: index + 1});
+            }
+            child_pv.clear();
+            let undo = board.make_move(mv);
+            let score = -self.negamax(board, depth - 1, 1, -INFINITY, -alpha, &mut child_pv);
+            board.unmake_move(mv, &undo);
+            if self.stopped{
+                break
+            }
+            if score > alpha{
+                alpha = score;
+                best_index = index;
+                pv.clear();
+                pv.push(*mv);
+                pv.extend_from_slice(&child_pv);
+            }
+        }
+        (best_index, alpha, pv)
+    }
+
+    fn negamax(&mut self, board: &mut Board, depth: u32, ply: i32, mut alpha: i32, beta: i32, pv: &mut Vec<Move>) -> i32{
+        if self.should_stop(){
+            return 0
+        }
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply as u32);
+        let entry = self.table.probe(board.hash());
+        if let Some(entry) = entry.filter(|entry| entry.depth >= depth){
+            let score = score_from_table(entry.score, ply);
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if cutoff{
+                pv.extend(entry.best_move);
+                return score
+            }
+        }
+        let mut moves = MoveList::new();
+        board.generate_moves(&mut moves);
+        if moves.is_empty(){
+            return Search::terminal_score(board, ply)
+        }
+        if depth == 0{
+            return evaluate(board)
+        }
+        order_moves(board, &mut moves);
+        // search the best move of the previous search of this position first
+        if let Some(index) = entry.and_then(|entry| entry.best_move).and_then(|best| moves.iter().position(|mv| *mv == best)){
+            moves[..=index].rotate_right(1);
+        }
+
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
+        for mv in moves{
+            child_pv.clear();
+            let undo = board.make_move(&mv);
+            let score = -self.negamax(board, depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+            board.unmake_move(&mv, &undo);
+            if self.stopped{
+                return 0
+            }
+            if score >= beta{
+                self.table.store(board.hash(), depth, Bound::Lower, score_to_table(score, ply), Some(mv));
+                return score
+            }
+            if score > alpha{
+                alpha = score;
+                best_move = Some(mv);
+                pv.clear();
+                pv.push(mv);
+                pv.extend_from_slice(&child_pv);
+            }
+        }
+        let bound = if best_move.is_some() {Bound::Exact} else {Bound::Upper};
+        self.table.store(board.hash(), depth, bound, score_to_table(alpha, ply), best_move);
+        alpha
+    }
+
+    /// Send the progress of the search to the callback, if any
+    fn info(&mut self, info: SearchInfo){
+        if let Some(callback) = self.on_info.as_mut(){
+            callback(info);
+        }
+    }
+
+    /// Check the node and time limits and the stop signal,
+    /// the clock is only read every CLOCK_CHECK_INTERVAL nodes
+    fn should_stop(&mut self) -> bool{
+        if let Some(limit) = self.node_limit{
+            if self.nodes >= limit{
+                self.stopped = true;
+            }
+        }
+        if self.nodes % CLOCK_CHECK_INTERVAL == 0{
+            self.check_ponderhit();
+            if self.time.hard_limit_reached() || self.signals.stop.load(Ordering::Relaxed){
+                self.stopped = true;
+            }
+        }
+        self.stopped
+    }
+
+    /// Start the clock when the ponder flag has been cleared
+    fn check_ponderhit(&mut self){
+        if self.time.is_pondering() && !self.signals.ponder.load(Ordering::Relaxed){
+            self.time.ponderhit();
+        }
+    }
+
+    /// Score of a position without legal move: mated or stalemate
+    fn terminal_score(board: &Board, ply: i32) -> i32{
+        if board.in_check(){
+            -MATE_SCORE + ply
+        } else{
+            0
+        }
+    }
+}
+impl Default for Search{
+    fn default() -> Self {
+        Search::new()
+    }
+}
+
+/// Sort moves to search captures first, most valuable victim first, then promotions
+fn order_moves(board: &Board, moves: &mut [Move]){
+    moves.sort_by_key(|mv| match board[&mv.to()] {
+        Some(victim) if mv.is_capture() => -piece_value(victim.kind),
+        _ if mv.is_promotion() => -1,
+        _ => 0,
+    })
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::engine::board::Board;
+    use crate::engine::search::{Search, SearchLimits, SearchResult, SearchInfo, mate_in, MATE_SCORE, MAX_DEPTH};
+    use crate::engine::time_manager::TimeManager;
+    use crate::engine::piece::Color;
+    use std::time::{Duration, Instant};
+    use std::thread;
+    use std::sync::atomic::Ordering;
+    use std::sync::{Arc, Mutex};
+
+    fn search_depth(board: &Board, depth: u32) -> SearchResult{
+        let limits = SearchLimits{depth: Some(depth), ..SearchLimits::default()};
+        Search::new().search(board, &limits, TimeManager::infinite())
+    }
+
+    #[test]
+    fn test_mate_in_one(){
+        let board = Board::new_from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1");
+        let result = search_depth(&board, 2);
+        assert_eq!(result.best_move.unwrap().to_uci(), "a1a8");
+        assert_eq!(result.score, MATE_SCORE - 1);
+    }
+
+    #[test]
+    fn test_mated(){
+        let board = Board::new_from_fen("R5k1/5ppp/8/8/8/8/5PPP/6K1 b - - 0 1");
+        let result = search_depth(&board, 3);
+        assert!(result.best_move.is_none());
+        assert_eq!(result.score, -MATE_SCORE);
+    }
+
+    #[test]
+    fn test_stalemate(){
+        let board = Board::new_from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        let result = search_depth(&board, 3);
+        assert!(result.best_move.is_none());
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    fn test_win_material(){
+        // the knight on e5 is hanging
+        let board = Board::new_from_fen("4k3/8/8/4n3/8/8/8/4RK2 w - - 0 1");
+        let result = search_depth(&board, 3);
+        assert_eq!(result.best_move.unwrap().to_uci(), "e1e5");
+    }
+
+    #[test]
+    fn test_avoid_stalemate(){
+        // Qf7 would stalemate, any other sensible queen move keeps the win
+        let board = Board::new_from_fen("7k/8/6K1/8/8/8/8/5Q2 w - - 0 1");
+        let result = search_depth(&board, 3);
+        assert_ne!(result.best_move.unwrap().to_uci(), "f1f7");
+        assert!(result.score > 0);
+    }
+
+    #[test]
+    fn test_depth_limit(){
+        let result = search_depth(&Board::new_board(), 3);
+        assert_eq!(result.depth, 3);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn test_node_limit(){
+        let limits = SearchLimits{nodes: Some(1000), ..SearchLimits::default()};
+        let mut search = Search::new();
+        let result = search.search(&Board::new_board(), &limits, TimeManager::infinite());
+        assert!(result.best_move.is_some());
+        assert!(search.nodes <= 1000);
+    }
+
+    #[test]
+    fn test_searchmoves(){
+        // a1a8 mates, but only the pawn moves are searched
+        let board = Board::new_from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1");
+        let searchmoves = vec!["h2h3".parse().unwrap(), "h2h4".parse().unwrap()];
+        let limits = SearchLimits{depth: Some(2), searchmoves, ..SearchLimits::default()};
+        let result = Search::new().search(&board, &limits, TimeManager::infinite());
+        assert!(["h2h3", "h2h4"].contains(&result.best_move.unwrap().to_uci().as_str()));
+
+        // no legal searchmove, all the moves are searched
+        let limits = SearchLimits{depth: Some(2), searchmoves: vec!["e2e4".parse().unwrap()], ..SearchLimits::default()};
+        let result = Search::new().search(&board, &limits, TimeManager::infinite());
+        assert_eq!(result.best_move.unwrap().to_uci(), "a1a8");
+    }
+
+    #[test]
+    fn test_mate_limit(){
+        // mate in 2: Re8+ Rxe8 Rxe8#
+        let board = Board::new_from_fen("3r2k1/5ppp/8/8/8/8/4RPPP/4R1K1 w - - 0 1");
+        let limits = SearchLimits{mate: Some(2), ..SearchLimits::default()};
+        let result = Search::new().search(&board, &limits, TimeManager::infinite());
+        assert_eq!(result.score, MATE_SCORE - 3);
+        assert!(["e1e8", "e2e8"].contains(&result.best_move.unwrap().to_uci().as_str()));
+        assert!(result.depth <= 3);
+
+        // the number of plies of a huge mate does not overflow
+        let limits = SearchLimits{mate: Some(3_000_000_000), ..SearchLimits::default()};
+        assert_eq!(limits.max_depth(), MAX_DEPTH);
+        let result = Search::new().search(&board, &limits, TimeManager::infinite());
+        assert_eq!(result.score, MATE_SCORE - 3);
+        assert!(result.depth <= 3);
+    }
+
+    #[test]
+    fn test_time_limit(){
+        let limits = SearchLimits{movetime: Some(Duration::from_millis(100)), ..SearchLimits::default()};
+        let time = TimeManager::new(&limits, Color::White, Duration::from_millis(0));
+        let start = Instant::now();
+        let result = Search::new().search(&Board::new_board(), &limits, time);
+        assert!(result.best_move.is_some());
+        assert!(start.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_stop_signal(){
+        let limits = SearchLimits{infinite: true, ..SearchLimits::default()};
+        let mut search = Search::new();
+        let signals = search.signals();
+        let handle = thread::spawn(move || search.search(&Board::new_board(), &limits, TimeManager::infinite()));
+        thread::sleep(Duration::from_millis(50));
+        signals.stop.store(true, Ordering::Relaxed);
+        assert!(handle.join().unwrap().best_move.is_some());
+    }
+
+    #[test]
+    fn test_ponderhit(){
+        let limits = SearchLimits{ponder: true, movetime: Some(Duration::from_millis(50)), ..SearchLimits::default()};
+        let mut search = Search::new();
+        let signals = search.signals();
+        signals.ponder.store(true, Ordering::Relaxed);
+        let time = TimeManager::new(&limits, Color::White, Duration::from_millis(0));
+        let start = Instant::now();
+        let handle = thread::spawn(move || search.search(&Board::new_board(), &limits, time));
+        thread::sleep(Duration::from_millis(100));
+        // still searching, the deadline is not applied while pondering
+        assert!(!handle.is_finished());
+        signals.ponder.store(false, Ordering::Relaxed);
+        assert!(handle.join().unwrap().best_move.is_some());
+        assert!(start.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_principal_variation(){
+        let board = Board::new_from_fen("3r2k1/5ppp/8/8/8/8/4RPPP/4R1K1 w - - 0 1");
+        let result = search_depth(&board, 3);
+        let pv: Vec<String> = result.pv.iter().map(|mv| mv.to_uci()).collect();
+        assert_eq!(pv.len(), 3);
+        assert_eq!(pv[0], result.best_move.unwrap().to_uci());
+        assert_eq!(pv[1], "d8e8");
+    }
+
+    #[test]
+    fn test_info_callback(){
+        let iterations = Arc::new(Mutex::new(Vec::new()));
+        let mut search = Search::new();
+        let sink = Arc::clone(&iterations);
+        search.set_info_callback(Box::new(move |info| {
+            if let SearchInfo::Iteration{depth, pv, nodes, ..} = info{
+                assert!(!pv.is_empty());
+                assert!(nodes > 0);
+                sink.lock().unwrap().push(depth);
+            }
+        }));
+        let limits = SearchLimits{depth: Some(3), ..SearchLimits::default()};
+        search.search(&Board::new_board(), &limits, TimeManager::infinite());
+        assert_eq!(*iterations.lock().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_transposition_table(){
+        let board = Board::new_from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+        let limits = SearchLimits{depth: Some(3), ..SearchLimits::default()};
+        let mut search = Search::new();
+        let first = search.search(&board, &limits, TimeManager::infinite());
+        let first_nodes = search.nodes;
+        // the second search reuses the results of the first one
+        let second = search.search(&board, &limits, TimeManager::infinite());
+        assert!(search.nodes < first_nodes);
+        assert_eq!(first.score, second.score);
+        assert_eq!(first.best_move, second.best_move);
+
+        search.clear_hash();
+        search.search(&board, &limits, TimeManager::infinite());
+        assert_eq!(search.nodes, first_nodes);
+    }
+
+    #[test]
+    fn test_mate_score_from_table(){
+        // mate in 2 found again at the root when the mating line is stored in the table
+        let board = Board::new_from_fen("3r2k1/5ppp/8/8/8/8/4RPPP/4R1K1 w - - 0 1");
+        let mut search = Search::new();
+        for depth in [3, 4]{
+            let limits = SearchLimits{depth: Some(depth), ..SearchLimits::default()};
+            assert_eq!(search.search(&board, &limits, TimeManager::infinite()).score, MATE_SCORE - 3);
+        }
+    }
+
+    #[test]
+    fn test_mate_in(){
+        assert_eq!(mate_in(MATE_SCORE - 1), Some(1));
+        assert_eq!(mate_in(MATE_SCORE - 3), Some(2));
+        assert_eq!(mate_in(-MATE_SCORE), Some(0));
+        assert_eq!(mate_in(-MATE_SCORE + 2), Some(-1));
+        assert_eq!(mate_in(250), None);
+    }
+}
\ No newline at end of file
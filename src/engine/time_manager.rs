@@ -0,0 +1,202 @@
+use std::time::{Duration, Instant};
+use crate::engine::piece::Color;
+use crate::engine::search::SearchLimits;
+
+/// Number of moves the remaining time is shared between when the GUI does not send `movestogo`
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+/// Factor between the soft and the hard deadline
+const HARD_LIMIT_FACTOR: u32 = 3;
+
+/// Turn the search limits into deadlines for the search.
+///
+/// The soft deadline is checked between two iterations of the search: no new iteration is started
+/// once it is reached. The hard deadline is checked during the search and abort it.
+pub struct TimeManager{
+    start: Instant,
+    soft: Option<Duration>,
+    hard: Option<Duration>,
+    /// No deadline is applied while pondering
+    pondering: bool,
+}
+impl TimeManager{
+    /// Create a time manager for the side to play, move_overhead is kept as a safety margin
+    /// for the communication with the GUI
+    pub fn new(limits: &SearchLimits, side: Color, move_overhead: Duration) -> Self{
+        let (soft, hard) = if limits.infinite{
+            (None, None)
+        } else if let Some(movetime) = limits.movetime{
+            let time = movetime.checked_sub(move_overhead).unwrap_or(movetime / 2);
+            (Some(time), Some(time))
+        } else{
+            let (own, other) = match side {
+                Color::White => ((limits.wtime, limits.winc), (limits.btime, limits.binc)),
+                Color::Black => ((limits.btime, limits.binc), (limits.wtime, limits.winc)),
+            };
+            // some GUIs only send the clock of the opponent, the clocks are assumed to be even
+            let (time, inc) = if own.0.is_some() {own} else {other};
+            match time {
+                Some(time) => {
+                    let (soft, hard) = TimeManager::allocate(time, inc.unwrap_or_default(), limits.movestogo, move_overhead);
+                    (Some(soft), Some(hard))
+                }
+                None => (None, None),
+            }
+        };
+        TimeManager{start: Instant::now(), soft, hard, pondering: limits.ponder}
+    }
+
+    /// Time manager without any deadline
+    pub fn infinite() -> Self{
+        TimeManager{start: Instant::now(), soft: None, hard: None, pondering: false}
+    }
+
+    /// Share the remaining time between the moves to go
+    fn allocate(time: Duration, inc: Duration, movestogo: Option<u32>, move_overhead: Duration) -> (Duration, Duration){
+        let available = time.saturating_sub(move_overhead);
+        let moves_to_go = movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let share = available / moves_to_go;
+        let soft = (share + inc * 3 / 4).min(available);
+        // an overrun keeps a fraction of their share for the other moves to go
+        let reserve = share / HARD_LIMIT_FACTOR * (moves_to_go - 1);
+        let hard = (soft * HARD_LIMIT_FACTOR).min(available.saturating_sub(reserve)).max(soft);
+        (soft, hard)
+    }
+
+    /// Is the search pondering
+    pub fn is_pondering(&self) -> bool{
+        self.pondering
+    }
+
+    /// The opponent played the expected move: the clock starts now
+    pub fn ponderhit(&mut self){
+        self.pondering = false;
+        self.start = Instant::now();
+    }
+
+    /// Time elapsed since the start of the search
+    pub fn elapsed(&self) -> Duration{
+        self.start.elapsed()
+    }
+
+    /// Soft deadline, if any
+    pub fn soft_limit(&self) -> Option<Duration>{
+        self.soft
+    }
+
+    /// Hard deadline, if any
+    pub fn hard_limit(&self) -> Option<Duration>{
+        self.hard
+    }
+
+    /// Should the search stop before starting a new iteration
+    pub fn soft_limit_reached(&self) -> bool{
+        !self.pondering && self.soft.is_some_and(|soft| self.elapsed() >= soft)
+    }
+
+    /// Should the search be aborted
+    pub fn hard_limit_reached(&self) -> bool{
+        !self.pondering && self.hard.is_some_and(|hard| self.elapsed() >= hard)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use std::time::Duration;
+    use crate::engine::piece::Color;
+    use crate::engine::search::SearchLimits;
+    use crate::engine::time_manager::TimeManager;
+
+    const OVERHEAD: Duration = Duration::from_millis(10);
+
+    fn ms(millis: u64) -> Option<Duration>{
+        Some(Duration::from_millis(millis))
+    }
+
+    #[test]
+    fn test_no_time_limit(){
+        let time = TimeManager::new(&SearchLimits::default(), Color::White, OVERHEAD);
+        assert_eq!(time.soft_limit(), None);
+        assert_eq!(time.hard_limit(), None);
+        assert!(!time.hard_limit_reached());
+
+        let limits = SearchLimits{infinite: true, wtime: ms(1000), ..SearchLimits::default()};
+        let time = TimeManager::new(&limits, Color::White, OVERHEAD);
+        assert_eq!(time.hard_limit(), None);
+    }
+
+    #[test]
+    fn test_movetime(){
+        let limits = SearchLimits{movetime: ms(1000), wtime: ms(100), ..SearchLimits::default()};
+        let time = TimeManager::new(&limits, Color::White, OVERHEAD);
+        assert_eq!(time.soft_limit(), ms(990));
+        assert_eq!(time.hard_limit(), ms(990));
+    }
+
+    #[test]
+    fn test_clock(){
+        let limits = SearchLimits{wtime: ms(60_000), btime: ms(30_010), ..SearchLimits::default()};
+        let white = TimeManager::new(&limits, Color::White, OVERHEAD);
+        let black = TimeManager::new(&limits, Color::Black, OVERHEAD);
+        assert_eq!(black.soft_limit(), ms(1000));
+        assert_eq!(black.hard_limit(), ms(3000));
+        assert!(white.soft_limit() > black.soft_limit());
+
+        // increment is added to the allocated time
+        let limits = SearchLimits{btime: ms(30_010), binc: ms(1000), ..SearchLimits::default()};
+        let time = TimeManager::new(&limits, Color::Black, OVERHEAD);
+        assert_eq!(time.soft_limit(), ms(1750));
+
+        // only the clock of the opponent is sent
+        let time = TimeManager::new(&limits, Color::White, OVERHEAD);
+        assert_eq!(time.soft_limit(), ms(1750));
+    }
+
+    #[test]
+    fn test_movestogo(){
+        let limits = SearchLimits{wtime: ms(10_010), movestogo: Some(10), ..SearchLimits::default()};
+        let time = TimeManager::new(&limits, Color::White, OVERHEAD);
+        assert_eq!(time.soft_limit(), ms(1000));
+
+        // more moves than the default are not capped
+        let limits = SearchLimits{wtime: ms(40_010), movestogo: Some(40), ..SearchLimits::default()};
+        let time = TimeManager::new(&limits, Color::White, OVERHEAD);
+        assert_eq!(time.soft_limit(), ms(1000));
+
+        // the hard limit keeps time for the last move before the time control
+        let limits = SearchLimits{wtime: ms(10_010), movestogo: Some(2), ..SearchLimits::default()};
+        let time = TimeManager::new(&limits, Color::White, OVERHEAD);
+        assert_eq!(time.soft_limit(), ms(5000));
+        assert_eq!(time.hard_limit(), Some(Duration::from_millis(10_000) - Duration::from_millis(5000) / 3));
+
+        // last move before the time control, all the time can be used
+        let limits = SearchLimits{wtime: ms(10_010), movestogo: Some(1), ..SearchLimits::default()};
+        let time = TimeManager::new(&limits, Color::White, OVERHEAD);
+        assert_eq!(time.soft_limit(), ms(10_000));
+        assert_eq!(time.hard_limit(), ms(10_000));
+    }
+
+    #[test]
+    fn test_low_time(){
+        // never go over the remaining time, even with a big increment
+        let limits = SearchLimits{wtime: ms(100), winc: ms(2000), ..SearchLimits::default()};
+        let time = TimeManager::new(&limits, Color::White, OVERHEAD);
+        assert_eq!(time.soft_limit(), ms(90));
+        assert_eq!(time.hard_limit(), ms(90));
+
+        let limits = SearchLimits{wtime: ms(0), ..SearchLimits::default()};
+        let time = TimeManager::new(&limits, Color::White, OVERHEAD);
+        assert_eq!(time.hard_limit(), ms(0));
+        assert!(time.hard_limit_reached());
+    }
+
+    #[test]
+    fn test_ponder(){
+        let limits = SearchLimits{ponder: true, wtime: ms(0), ..SearchLimits::default()};
+        let mut time = TimeManager::new(&limits, Color::White, OVERHEAD);
+        assert!(time.is_pondering());
+        assert!(!time.hard_limit_reached());
+        time.ponderhit();
+        assert!(!time.is_pondering());
+        assert!(time.hard_limit_reached());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,176 @@
+use std::mem::size_of;
+use crate::engine::moves::Move;
+
+/// Number of entries sharing the same index
+const BUCKET_SIZE: usize = 4;
+/// Number of buckets sampled to compute hashfull
+const HASHFULL_SAMPLE: usize = 250;
+
+/// Kind of score stored in an entry
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Bound{
+    /// Exact score of the position
+    Exact,
+    /// The search failed high: the score is at least this
+    Lower,
+    /// The search failed low: the score is at most this
+    Upper,
+}
+
+/// Result of the search of a position
+#[derive(Debug, Copy, Clone)]
+pub struct Entry{
+    /// Zobrist hash of the position
+    pub key: u64,
+    /// Depth the position was searched at
+    pub depth: u32,
+    pub bound: Bound,
+    pub score: i32,
+    /// Best move, if the search found one
+    pub best_move: Option<Move>,
+    /// Search the entry was stored during
+    age: u8,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+struct Bucket{
+    entries: [Option<Entry>; BUCKET_SIZE],
+}
+
+/// Fixed size hash table of searched positions, indexed by their Zobrist hash
+pub struct TranspositionTable{
+    buckets: Vec<Bucket>,
+    /// Incremented on each new search, so entries from previous searches are replaced first
+    age: u8,
+}
+impl TranspositionTable{
+    /// Create a table using at most size_mb MB
+    pub fn new(size_mb: usize) -> Self{
+        let count = (size_mb * 1024 * 1024 / size_of::<Bucket>()).max(1);
+        TranspositionTable{buckets: vec![Bucket::default(); count], age: 0}
+    }
+
+    /// Resize the table, its content is lost
+    pub fn resize(&mut self, size_mb: usize){
+        *self = TranspositionTable::new(size_mb);
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self){
+        self.buckets.fill(Bucket::default());
+        self.age = 0;
+    }
+
+    /// Start a new search, the entries of the previous ones are kept but aged
+    pub fn new_search(&mut self){
+        self.age = self.age.wrapping_add(1);
+    }
+
+    fn bucket(&self, key: u64) -> usize{
+        (key % self.buckets.len() as u64) as usize
+    }
+
+    /// Get the entry of a position
+    pub fn probe(&self, key: u64) -> Option<Entry>{
+        self.buckets[self.bucket(key)].entries.iter()
+            .flatten()
+            .find(|entry| entry.key == key)
+            .copied()
+    }
+
+    /// Store the result of the search of a position.
+    ///
+    /// The entry of the same position is replaced, else an empty slot, else the entry with the
+    /// lowest depth, entries from previous searches being replaced first.
+    pub fn store(&mut self, key: u64, depth: u32, bound: Bound, score: i32, best_move: Option<Move>){
+        let age = self.age;
+        let index = self.bucket(key);
+        let entries = &mut self.buckets[index].entries;
+        let slot = match entries.iter().position(|entry| entry.is_some_and(|entry| entry.key == key)) {
+            Some(slot) => slot,
+            None => entries.iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| match entry {
+                    None => i64::MIN,
+                    Some(entry) => entry.depth as i64 - 8 * age.wrapping_sub(entry.age) as i64,
+                })
+                .map(|(slot, _)| slot)
+                .unwrap(),
+        };
+        // keep the known best move when the new search did not find one
+        let best_move = best_move.or_else(|| entries[slot].filter(|entry| entry.key == key).and_then(|entry| entry.best_move));
+        entries[slot] = Some(Entry{key, depth, bound, score, best_move, age});
+    }
+
+    /// Approximate usage of the table, in permill, counting only the entries of the current search
+    pub fn hashfull(&self) -> u32{
+        let sample = &self.buckets[..HASHFULL_SAMPLE.min(self.buckets.len())];
+        let used = sample.iter()
+            .flat_map(|bucket| bucket.entries.iter().flatten())
+            .filter(|entry| entry.age == self.age)
+            .count();
+        (used * 1000 / (sample.len() * BUCKET_SIZE)) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::engine::board::Board;
+    use crate::engine::moves::Move;
+    use crate::engine::transposition::{TranspositionTable, Bound, BUCKET_SIZE};
+
+    #[test]
+    fn test_store_probe(){
+        let mut table = TranspositionTable::new(1);
+        let board = Board::new_board();
+        let mv = Move::new_on_board("e2e4", &board);
+        assert!(table.probe(board.hash()).is_none());
+        table.store(board.hash(), 3, Bound::Exact, 25, Some(mv));
+        let entry = table.probe(board.hash()).unwrap();
+        assert_eq!((entry.depth, entry.bound, entry.score, entry.best_move), (3, Bound::Exact, 25, Some(mv)));
+
+        // the best move is kept when the new result has none
+        table.store(board.hash(), 4, Bound::Upper, -10, None);
+        let entry = table.probe(board.hash()).unwrap();
+        assert_eq!((entry.depth, entry.bound, entry.best_move), (4, Bound::Upper, Some(mv)));
+
+        table.clear();
+        assert!(table.probe(board.hash()).is_none());
+    }
+
+    #[test]
+    fn test_replacement(){
+        let mut table = TranspositionTable::new(1);
+        let buckets = table.buckets.len() as u64;
+        // keys of the same bucket
+        let key = |i: u64| 7 + i * buckets;
+        for i in 0..BUCKET_SIZE as u64{
+            table.store(key(i), 10 + i as u32, Bound::Exact, 0, None);
+        }
+        // the shallowest entry is replaced
+        table.store(key(10), 1, Bound::Exact, 0, None);
+        assert!(table.probe(key(0)).is_none());
+        assert!(table.probe(key(10)).is_some());
+
+        // entries from an old search are replaced before deeper ones
+        table.new_search();
+        table.store(key(11), 5, Bound::Exact, 0, None);
+        table.store(key(12), 1, Bound::Exact, 0, None);
+        assert!(table.probe(key(11)).is_some());
+        assert!(table.probe(key(12)).is_some());
+        assert!(table.probe(key(1)).is_none());
+        assert_eq!(table.buckets[7].entries.iter().flatten().count(), BUCKET_SIZE);
+    }
+
+    #[test]
+    fn test_hashfull(){
+        let mut table = TranspositionTable::new(1);
+        assert_eq!(table.hashfull(), 0);
+        for key in 0..table.buckets.len() as u64{
+            table.store(key, 1, Bound::Exact, 0, None);
+        }
+        assert_eq!(table.hashfull(), 1000 / BUCKET_SIZE as u32);
+        table.new_search();
+        assert_eq!(table.hashfull(), 0);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::engine::board::{Board, Case, Castle};
+use crate::engine::piece::{Piece, Color};
+
+/// Random keys used to compute the Zobrist hash of a board
+/// (https://www.chessprogramming.org/Zobrist_Hashing)
+pub struct ZobristKeys{
+    /// One key per piece and per case
+    pieces: [[u64; 64]; 12],
+    /// Xored when black is to play
+    side: u64,
+    /// One key per castle right: white king, white queen, black king, black queen
+    castle: [u64; 4],
+    /// One key per column of the 'en passant' case
+    en_passant: [u64; 8],
+}
+
+/// Keys generated at compile time with a fixed seed, so hashes are stable between runs
+pub static KEYS: ZobristKeys = ZobristKeys::generate(0x9E37_79B9_7F4A_7C15);
+
+/// xorshift64* pseudo random generator step, return the new state and the random number
+pub(crate) const fn next_random(state: u64) -> (u64, u64){
+    let mut x = state;
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    (x, x.wrapping_mul(0x2545_F491_4F6C_DD1D))
+}
+
+impl ZobristKeys{
+    const fn generate(seed: u64) -> Self{
+        let mut keys = ZobristKeys{pieces: [[0; 64]; 12], side: 0, castle: [0; 4], en_passant: [0; 8]};
+        let mut state = seed;
+        let mut random;
+        let mut piece = 0;
+        while piece < 12{
+            let mut case = 0;
+            while case < 64{
+                (state, random) = next_random(state);
+                keys.pieces[piece][case] = random;
+                case += 1;
+            }
+            piece += 1;
+        }
+        (state, random) = next_random(state);
+        keys.side = random;
+        let mut i = 0;
+        while i < 4{
+            (state, random) = next_random(state);
+            keys.castle[i] = random;
+            i += 1;
+        }
+        let mut i = 0;
+        while i < 8{
+            (state, random) = next_random(state);
+            keys.en_passant[i] = random;
+            i += 1;
+        }
+        keys
+    }
+
+    /// Key of a piece standing on a case
+    pub fn piece(&self, piece: &Piece, case: &Case) -> u64{
+        self.pieces[piece.kind.index() + 6 * piece.color.index()][case.index()]
+    }
+
+    /// Key of the side to move
+    pub fn side(&self, side: Color) -> u64{
+        match side {
+            Color::White => 0,
+            Color::Black => self.side,
+        }
+    }
+
+    /// Key of the castle rights
+    pub fn castle(&self, castle: &Castle) -> u64{
+        let rights = [castle.white_king, castle.white_queen, castle.black_king, castle.black_queen];
+        rights.iter().zip(self.castle.iter())
+            .filter(|(&right, _)| right)
+            .fold(0, |key, (_, castle_key)| key ^ castle_key)
+    }
+
+    /// Key of the 'en passant' case
+    pub fn en_passant(&self, en_passant: Option<Case>) -> u64{
+        match en_passant {
+            Some(case) => self.en_passant[case.get_column()],
+            None => 0,
+        }
+    }
+}
+
+impl Board{
+    /// Compute the Zobrist hash of the board from scratch
+    pub fn compute_hash(&self) -> u64{
+        let mut hash = KEYS.side(self.side) ^ KEYS.castle(&self.castle) ^ KEYS.en_passant(self.en_passant);
+        for i in 0..64{
+            if let Some(piece) = self[i]{
+                hash ^= KEYS.piece(&piece, &Case::new(i));
+            }
+        }
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::engine::board::Board;
+    use crate::engine::moves::Move;
+
+    /// Check the incremental hash against a full computation on all positions of the tree
+    fn check_hash(board: &Board, depth: u32){
+        assert_eq!(board.hash(), board.compute_hash(), "{}", board.to_fen());
+        if depth == 0{
+            return
+        }
+        for mv in board.get_moves(){
+            check_hash(&board.apply_move(&mv), depth - 1);
+        }
+    }
+
+    #[test]
+    fn test_incremental_hash(){
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 0",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 0",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        ].iter(){
+            check_hash(&Board::new_from_fen(fen), 3);
+        }
+    }
+
+    #[test]
+    fn test_transposition(){
+        let mut board_1 = Board::new_board();
+        for mv in ["g1f3", "g8f6", "b1c3", "b8c6"].iter(){
+            board_1 = board_1.apply_move(&Move::new_on_board(mv, &board_1));
+        }
+        let mut board_2 = Board::new_board();
+        for mv in ["b1c3", "b8c6", "g1f3", "g8f6"].iter(){
+            board_2 = board_2.apply_move(&Move::new_on_board(mv, &board_2));
+        }
+        assert_eq!(board_1.hash(), board_2.hash());
+        assert_ne!(board_1.hash(), Board::new_board().hash());
+
+        // same pieces, different side to move, castle rights or 'en passant'
+        let hashes = [
+            Board::new_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").hash(),
+            Board::new_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").hash(),
+            Board::new_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1").hash(),
+            Board::new_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w Kkq - 0 1").hash(),
+        ];
+        for i in 0..hashes.len(){
+            for j in i + 1..hashes.len(){
+                assert_ne!(hashes[i], hashes[j]);
+            }
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! Chess engine: board representation, legal move generation, evaluation and search.
+//! The `chess` binary is the UCI front end of this library
+pub mod engine;
+
+pub use crate::engine::board::{Board, Case};
+pub use crate::engine::error::ChessError;
+pub use crate::engine::moves::{Move, MoveKind};
+pub use crate::engine::move_list::MoveList;
+pub use crate::engine::piece::{Color, Piece, PieceKind};
+pub use crate::engine::eval::evaluate;
+pub use crate::engine::perft::{perft, perft_with, divide, PerftMode};
+pub use crate::engine::search::{Search, SearchLimits, SearchResult};
+pub use crate::engine::time_manager::TimeManager;
+
+/// Generate the sliding piece attack tables, otherwise the first move generation pays for it
+pub fn init(){
+    engine::magic::magic_tables();
+}
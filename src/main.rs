@@ -1,60 +1,31 @@
 use std::io;
-mod engine;
+use std::io::BufRead;
+use chess::engine;
+mod uci;
+
+use crate::uci::session::Session;
 
 fn main() {
     let stdin = io::stdin();
+    let mut session = Session::new();
+    chess::init(); // generate the tables before the first search
     loop {
-        let mut input = String::new();
-        stdin.read_line(&mut input).unwrap();
-        uci_parser::parse_line(&input);
-    }
-}
-
-mod uci_parser {
-
-    pub fn parse_line(line: &str){
-        let split_line: Vec<&str> = line[..(line.len()-1)].split(' ').collect();
-        if split_line.is_empty() {
-            eprintln!("Empty input!")
-        }
-        eprintln!("got: {:?}", split_line);
-        let mut board = Board::new_empty_board();
-        match split_line[0] {
-            "uci" => parse_uci(),
-            "isready" => parse_isready(),
-            "ucinewgame" => parse_ucinewgame(),
-            "position" => parse_position(&split_line[1..], &mut board),
-            "go" => println!("bestmove a7a5"),
-            _ => eprintln!("Unsuported opperation : {}", line)
-        }
-    }
-
-    pub fn parse_uci(){
-        println!("uciok"); // acknowledge the uci mode
-    }
-
-    pub fn parse_isready(){
-        println!("readyok"); // acknowledge the engine is ready
-    }
-
-    pub fn parse_ucinewgame(){
-        ()
-    }
-
-    use crate::engine::board::Board;
-
-    pub fn parse_position(details: &[&str], board: &mut Board){
-        match details[0]{
-            "startpos"=> *board = Board::new_board(),
-            fen=> *board = Board::new_from_fen(fen),
-        }
-        if !details.is_empty(){
-            for mv in details[2..details.len()].to_vec(){
-                println!("mv: {}", mv);
-                *board = board.apply_move(&mv.parse().unwrap());
+        // read bytes, a line that is not valid UTF-8 is decoded lossily instead of stopping the engine
+        let mut input = Vec::new();
+        match stdin.lock().read_until(b'\n', &mut input) {
+            Ok(0) => {
+                session.stop();
+                break // end of input
             }
+            Ok(_) => {}
+            Err(err) => {
+                eprintln!("Can not read the input: {}", err);
+                session.stop();
+                break
+            }
+        }
+        if !uci::parse_line(&String::from_utf8_lossy(&input), &mut session) {
+            break
         }
-        println!("new mv \n{}", board);
-        println!("fen: {}", board.to_fen());
     }
 }
\ No newline at end of file
@@ -0,0 +1,141 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::str::FromStr;
+use std::time::Duration;
+use crate::engine::moves::Move;
+use crate::engine::search::{SearchLimits, MAX_DEPTH};
+
+/// Error returned when a `go` command can not be understood
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GoParseError{
+    /// A parameter is not followed by its value
+    MissingValue(String),
+    /// The value of a parameter is not a valid number
+    InvalidValue(String, String),
+    /// Unknown parameter
+    UnexpectedToken(String),
+}
+impl fmt::Display for GoParseError{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GoParseError::MissingValue(param) => write!(f, "missing value for '{}'", param),
+            GoParseError::InvalidValue(param, value) => write!(f, "invalid value '{}' for '{}'", value, param),
+            GoParseError::UnexpectedToken(token) => write!(f, "unexpected token '{}'", token),
+        }
+    }
+}
+
+/// A parsed `go` command
+#[derive(Debug)]
+pub struct GoCommand{
+    /// Limits of the search
+    pub limits: SearchLimits,
+    /// Depth of a perft to run instead of the search, set by `go perft <depth>`
+    pub perft: Option<u32>,
+}
+impl GoCommand{
+    /// Parse the tokens following the `go` keyword
+    pub fn parse(details: &[&str]) -> Result<Self, GoParseError>{
+        let mut limits = SearchLimits::default();
+        let mut perft = None;
+        let mut tokens = details.iter().peekable();
+        while let Some(&token) = tokens.next(){
+            match token {
+                "wtime" => limits.wtime = Some(parse_time(token, tokens.next())?),
+                "btime" => limits.btime = Some(parse_time(token, tokens.next())?),
+                "winc" => limits.winc = Some(parse_time(token, tokens.next())?),
+                "binc" => limits.binc = Some(parse_time(token, tokens.next())?),
+                "movetime" => limits.movetime = Some(parse_time(token, tokens.next())?),
+                "movestogo" => limits.movestogo = Some(parse_value(token, tokens.next())?),
+                "depth" => limits.depth = Some(parse_value(token, tokens.next())?),
+                "nodes" => limits.nodes = Some(parse_value(token, tokens.next())?),
+                "mate" => limits.mate = Some(parse_value(token, tokens.next())?),
+                "infinite" => limits.infinite = true,
+                "ponder" => limits.ponder = true,
+                // the moves follow until the next parameter
+                "searchmoves" => while let Some(mv) = tokens.peek().and_then(|mv| mv.parse::<Move>().ok()){
+                    limits.searchmoves.push(mv);
+                    tokens.next();
+                },
+                // a divide needs at least one move, and deeper trees would overflow the stack
+                "perft" => match parse_value(token, tokens.next())? {
+                    depth @ 1..=MAX_DEPTH => perft = Some(depth),
+                    depth => return Err(GoParseError::InvalidValue(token.to_string(), depth.to_string())),
+                },
+                _ => return Err(GoParseError::UnexpectedToken(token.to_string())),
+            }
+        }
+        Ok(GoCommand{limits, perft})
+    }
+}
+
+/// Parse the value following a parameter
+fn parse_value<T: FromStr>(param: &str, value: Option<&&str>) -> Result<T, GoParseError>{
+    let value = value.ok_or_else(|| GoParseError::MissingValue(param.to_string()))?;
+    value.parse().map_err(|_| GoParseError::InvalidValue(param.to_string(), value.to_string()))
+}
+
+/// Parse a time in milliseconds, some GUIs send negative times when the engine is late
+fn parse_time(param: &str, value: Option<&&str>) -> Result<Duration, GoParseError>{
+    let millis: i64 = parse_value(param, value)?;
+    Ok(Duration::from_millis(millis.max(0) as u64))
+}
+
+#[cfg(test)]
+mod tests{
+    use std::time::Duration;
+    use crate::engine::search::SearchLimits;
+    use crate::uci::go::{GoCommand, GoParseError};
+
+    fn parse(command: &str) -> Result<SearchLimits, GoParseError>{
+        GoCommand::parse(&command.split_whitespace().collect::<Vec<&str>>()).map(|go| go.limits)
+    }
+
+    #[test]
+    fn test_parse_clock(){
+        let limits = parse("wtime 300000 btime 299000 winc 2000 binc 1000 movestogo 40").unwrap();
+        assert_eq!(limits, SearchLimits{
+            wtime: Some(Duration::from_millis(300_000)),
+            btime: Some(Duration::from_millis(299_000)),
+            winc: Some(Duration::from_millis(2000)),
+            binc: Some(Duration::from_millis(1000)),
+            movestogo: Some(40),
+            ..SearchLimits::default()
+        });
+        assert_eq!(parse("wtime -150").unwrap().wtime, Some(Duration::from_millis(0)));
+    }
+
+    #[test]
+    fn test_parse_limits(){
+        assert_eq!(parse("").unwrap(), SearchLimits::default());
+        assert_eq!(parse("depth 6").unwrap().depth, Some(6));
+        assert_eq!(parse("nodes 100000").unwrap().nodes, Some(100_000));
+        assert_eq!(parse("mate 3").unwrap().mate, Some(3));
+        assert_eq!(parse("movetime 500").unwrap().movetime, Some(Duration::from_millis(500)));
+        assert!(parse("infinite").unwrap().infinite);
+        assert!(parse("ponder wtime 1000 btime 1000").unwrap().ponder);
+        let limits = parse("searchmoves e2e4 a7a8q depth 3").unwrap();
+        assert_eq!(limits.searchmoves.iter().map(|mv| mv.to_uci()).collect::<Vec<String>>(), vec!["e2e4", "a7a8q"]);
+        assert_eq!(limits.depth, Some(3));
+        assert!(parse("searchmoves").unwrap().searchmoves.is_empty());
+    }
+
+    #[test]
+    fn test_parse_perft(){
+        let command = GoCommand::parse(&["perft", "5"]).unwrap();
+        assert_eq!(command.perft, Some(5));
+        assert_eq!(command.limits, SearchLimits::default());
+        assert_eq!(GoCommand::parse(&[]).unwrap().perft, None);
+        assert_eq!(parse("perft").err(), Some(GoParseError::MissingValue("perft".to_string())));
+        assert_eq!(parse("perft 0").err(), Some(GoParseError::InvalidValue("perft".to_string(), "0".to_string())));
+        assert_eq!(parse("perft 1000000").err(), Some(GoParseError::InvalidValue("perft".to_string(), "1000000".to_string())));
+    }
+
+    #[test]
+    fn test_parse_errors(){
+        assert_eq!(parse("depth").err(), Some(GoParseError::MissingValue("depth".to_string())));
+        assert_eq!(parse("depth x").err(), Some(GoParseError::InvalidValue("depth".to_string(), "x".to_string())));
+        assert_eq!(parse("depth -1").err(), Some(GoParseError::InvalidValue("depth".to_string(), "-1".to_string())));
+        assert_eq!(parse("fast").err(), Some(GoParseError::UnexpectedToken("fast".to_string())));
+    }
+}
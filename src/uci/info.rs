@@ -0,0 +1,71 @@
+use crate::engine::moves::Move;
+use crate::engine::search::{SearchInfo, mate_in};
+
+/// Format the progress of the search as an uci `info` line
+pub fn format_info(info: &SearchInfo) -> String{
+    match info {
+        SearchInfo::Iteration{depth, seldepth, score, nodes, time, hashfull, pv} => {
+            let millis = time.as_millis() as u64;
+            let nps = nodes * 1000 / millis.max(1);
+            let pv: Vec<String> = pv.iter().map(|mv| mv.to_uci()).collect();
+            format!("info depth {} seldepth {} score {} nodes {} nps {} hashfull {} time {} pv {}",
+                    depth, seldepth, format_score(*score), nodes, nps, hashfull, millis, pv.join(" "))
+        }
+        SearchInfo::CurrentMove{depth, mv, number} =>
+            format!("info depth {} currmove {} currmovenumber {}", depth, mv.to_uci(), number),
+    }
+}
+
+/// Format the nodes under each root move and their total, like Stockfish `go perft`
+pub fn format_divide(divide: &[(Move, u64)]) -> String{
+    let mut text: String = divide.iter().map(|(mv, nodes)| format!("{}: {}\n", mv.to_uci(), nodes)).collect();
+    text.push_str(&format!("\nNodes searched: {}", divide.iter().map(|(_, nodes)| nodes).sum::<u64>()));
+    text
+}
+
+/// Format a score as `cp <centipawns>` or `mate <moves>`
+fn format_score(score: i32) -> String{
+    match mate_in(score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", score),
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use std::time::Duration;
+    use crate::engine::board::Board;
+    use crate::engine::moves::Move;
+    use crate::engine::search::{SearchInfo, MATE_SCORE};
+    use crate::uci::info::{format_info, format_divide};
+
+    #[test]
+    fn test_format_iteration(){
+        let board = Board::new_board();
+        let e4 = Move::new_on_board("e2e4", &board);
+        let e5 = Move::new_on_board("e7e5", &board.apply_move(&e4));
+        let pv = [e4, e5];
+        let info = SearchInfo::Iteration{depth: 2, seldepth: 3, score: 35, nodes: 1500, time: Duration::from_millis(500), hashfull: 12, pv: &pv};
+        assert_eq!(format_info(&info), "info depth 2 seldepth 3 score cp 35 nodes 1500 nps 3000 hashfull 12 time 500 pv e2e4 e7e5");
+
+        let info = SearchInfo::Iteration{depth: 3, seldepth: 3, score: MATE_SCORE - 3, nodes: 10, time: Duration::from_millis(0), hashfull: 0, pv: &pv};
+        assert_eq!(format_info(&info), "info depth 3 seldepth 3 score mate 2 nodes 10 nps 10000 hashfull 0 time 0 pv e2e4 e7e5");
+
+        let info = SearchInfo::Iteration{depth: 3, seldepth: 3, score: -MATE_SCORE + 2, nodes: 10, time: Duration::from_millis(10), hashfull: 0, pv: &pv};
+        assert_eq!(format_info(&info), "info depth 3 seldepth 3 score mate -1 nodes 10 nps 1000 hashfull 0 time 10 pv e2e4 e7e5");
+    }
+
+    #[test]
+    fn test_format_current_move(){
+        let board = Board::new_board();
+        let info = SearchInfo::CurrentMove{depth: 7, mv: Move::new_on_board("g1f3", &board), number: 4};
+        assert_eq!(format_info(&info), "info depth 7 currmove g1f3 currmovenumber 4");
+    }
+
+    #[test]
+    fn test_format_divide(){
+        let board = Board::new_board();
+        let divide = [(Move::new_on_board("a2a3", &board), 380), (Move::new_on_board("g1f3", &board), 440)];
+        assert_eq!(format_divide(&divide), "a2a3: 380\ng1f3: 440\n\nNodes searched: 820");
+    }
+}
@@ -0,0 +1,143 @@
+pub mod session;
+pub mod position;
+pub mod go;
+pub mod info;
+pub mod options;
+
+use crate::engine::perft::{divide, PerftMode};
+use crate::uci::session::Session;
+use crate::uci::position::PositionCommand;
+use crate::uci::go::GoCommand;
+use crate::uci::info::format_divide;
+
+/// Handle a line sent by the GUI, return false when the engine has to quit
+pub fn parse_line(line: &str, session: &mut Session) -> bool{
+    let split_line: Vec<&str> = line.split_whitespace().collect();
+    if split_line.is_empty() {
+        eprintln!("Empty input!");
+        return true
+    }
+    match split_line[0] {
+        "uci" => parse_uci(session),
+        "isready" => parse_isready(),
+        "ucinewgame" => parse_ucinewgame(session),
+        "setoption" => parse_setoption(&split_line[1..], session),
+        "position" => parse_position(&split_line[1..], session),
+        "go" => parse_go(&split_line[1..], session),
+        "stop" => parse_stop(session),
+        "ponderhit" => parse_ponderhit(session),
+        "quit" => {
+            parse_quit(session);
+            return false
+        }
+        _ => eprintln!("Unsuported opperation : {}", line)
+    }
+    true
+}
+
+pub fn parse_uci(session: &Session){
+    println!("id name {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    println!("id author menagerm");
+    for option in session.options.iter(){
+        println!("{}", option);
+    }
+    println!("uciok"); // acknowledge the uci mode
+}
+
+pub fn parse_isready(){
+    println!("readyok"); // acknowledge the engine is ready
+}
+
+pub fn parse_ucinewgame(session: &mut Session){
+    session.new_game();
+}
+
+/// Parse `setoption name <name> [value <value>]`, name and value may contain spaces
+pub fn parse_setoption(details: &[&str], session: &mut Session){
+    if details.first() != Some(&"name"){
+        eprintln!("Invalid setoption command: expected 'name'");
+        return
+    }
+    let (name, value) = match details.iter().position(|&token| token == "value") {
+        Some(index) => (details[1..index].join(" "), Some(details[index + 1..].join(" "))),
+        None => (details[1..].join(" "), None),
+    };
+    if let Err(err) = session.set_option(&name, value.as_deref()){
+        eprintln!("Invalid setoption command: {}", err);
+    }
+}
+
+pub fn parse_position(details: &[&str], session: &mut Session){
+    match PositionCommand::parse(details) {
+        Ok(command) => {
+            // the moves are checked before the session is touched, so an invalid
+            // command keeps the previous position
+            let mut board = command.board;
+            let mut moves = Vec::with_capacity(command.moves.len());
+            for mv in command.moves{
+                match board.parse_legal_move(mv) {
+                    Ok(mv) => {
+                        board = board.apply_move(&mv);
+                        moves.push(mv);
+                    }
+                    Err(err) => {
+                        eprintln!("Invalid position command: {}", err);
+                        return
+                    }
+                }
+            }
+            session.set_position(command.board);
+            for mv in moves{
+                session.play_move(mv);
+            }
+        }
+        Err(err) => eprintln!("Invalid position command: {}", err),
+    }
+}
+
+pub fn parse_go(details: &[&str], session: &mut Session){
+    match GoCommand::parse(details) {
+        Ok(GoCommand{perft: Some(depth), ..}) => {
+            session.stop(); // the search would print between the lines of the divide
+            println!("{}", format_divide(&divide(&session.board, depth, &PerftMode::default())))
+        }
+        Ok(command) => session.go(command.limits),
+        Err(err) => eprintln!("Invalid go command: {}", err),
+    }
+}
+
+pub fn parse_stop(session: &mut Session){
+    session.stop();
+}
+
+pub fn parse_ponderhit(session: &mut Session){
+    session.ponderhit();
+}
+
+pub fn parse_quit(session: &mut Session){
+    session.stop();
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::uci::parse_position;
+    use crate::uci::session::Session;
+
+    fn position(command: &str, session: &mut Session){
+        parse_position(&command.split_whitespace().collect::<Vec<&str>>(), session);
+    }
+
+    #[test]
+    fn test_parse_position(){
+        let mut session = Session::new();
+        position("startpos moves e2e4 c7c5", &mut session);
+        let fen = "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2";
+        assert_eq!(session.board.to_fen(), fen);
+        assert_eq!(session.history.len(), 2);
+
+        // the king can not move to e7, the previous position is kept
+        position("startpos moves e2e4 c7c5 g1f3 e8e7 f1c4", &mut session);
+        assert_eq!(session.board.to_fen(), fen);
+        assert_eq!(session.history.len(), 2);
+    }
+}
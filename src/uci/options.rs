@@ -0,0 +1,260 @@
+use std::fmt;
+use std::fmt::Formatter;
+
+/// Size of the transposition table in MB
+pub const HASH: &str = "Hash";
+/// Number of search threads
+pub const THREADS: &str = "Threads";
+/// Number of principal variations to report
+pub const MULTI_PV: &str = "MultiPV";
+/// Empty the transposition table
+pub const CLEAR_HASH: &str = "Clear Hash";
+/// The GUI may ask the engine to ponder
+pub const PONDER: &str = "Ponder";
+/// Time in ms kept as a safety margin for the communication with the GUI
+pub const MOVE_OVERHEAD: &str = "Move Overhead";
+
+/// Error returned when an option can not be set
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum OptionError{
+    /// No option has this name
+    UnknownOption(String),
+    /// The option needs a value
+    MissingValue(String),
+    /// The value is not valid for the option
+    InvalidValue(String, String),
+}
+impl fmt::Display for OptionError{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionError::UnknownOption(name) => write!(f, "unknown option '{}'", name),
+            OptionError::MissingValue(name) => write!(f, "missing value for option '{}'", name),
+            OptionError::InvalidValue(name, value) => write!(f, "invalid value '{}' for option '{}'", value, name),
+        }
+    }
+}
+
+/// Type of an uci option, with its constraints
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum OptionType{
+    Spin{min: i64, max: i64},
+    Check,
+    Combo{values: Vec<String>},
+    String,
+    Button,
+}
+
+/// Value of an uci option
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum OptionValue{
+    Spin(i64),
+    Check(bool),
+    Combo(String),
+    String(String),
+    Button,
+}
+
+/// An option the GUI can set with `setoption`
+#[derive(Debug, Clone)]
+pub struct UciOption{
+    pub name: String,
+    pub kind: OptionType,
+    pub default: OptionValue,
+    pub value: OptionValue,
+}
+impl UciOption{
+    /// Parse a value for this option
+    fn parse_value(&self, value: Option<&str>) -> Result<OptionValue, OptionError>{
+        let invalid = |value: &str| OptionError::InvalidValue(self.name.clone(), value.to_string());
+        if let OptionType::Button = self.kind{
+            return Ok(OptionValue::Button)
+        }
+        let value = value.ok_or_else(|| OptionError::MissingValue(self.name.clone()))?;
+        match &self.kind {
+            OptionType::Spin{min, max} => match value.parse() {
+                Ok(spin) if spin >= *min && spin <= *max => Ok(OptionValue::Spin(spin)),
+                _ => Err(invalid(value)),
+            }
+            OptionType::Check => match value {
+                "true" => Ok(OptionValue::Check(true)),
+                "false" => Ok(OptionValue::Check(false)),
+                _ => Err(invalid(value)),
+            }
+            OptionType::Combo{values} => values.iter()
+                .find(|var| var.eq_ignore_ascii_case(value))
+                .map(|var| OptionValue::Combo(var.clone()))
+                .ok_or_else(|| invalid(value)),
+            OptionType::String => match value {
+                "<empty>" => Ok(OptionValue::String(String::new())),
+                _ => Ok(OptionValue::String(value.to_string())),
+            }
+            OptionType::Button => Ok(OptionValue::Button),
+        }
+    }
+}
+/// Format the option as advertised in the answer to `uci`
+impl fmt::Display for UciOption{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "option name {} type ", self.name)?;
+        match (&self.kind, &self.default) {
+            (OptionType::Spin{min, max}, OptionValue::Spin(default)) =>
+                write!(f, "spin default {} min {} max {}", default, min, max),
+            (OptionType::Check, OptionValue::Check(default)) => write!(f, "check default {}", default),
+            (OptionType::Combo{values}, OptionValue::Combo(default)) => {
+                write!(f, "combo default {}", default)?;
+                values.iter().try_for_each(|var| write!(f, " var {}", var))
+            }
+            (OptionType::String, OptionValue::String(default)) if default.is_empty() => write!(f, "string default <empty>"),
+            (OptionType::String, OptionValue::String(default)) => write!(f, "string default {}", default),
+            _ => write!(f, "button"),
+        }
+    }
+}
+
+/// Registry of the engine options
+#[derive(Debug, Clone)]
+pub struct Options{
+    options: Vec<UciOption>,
+}
+impl Options{
+    /// Create a registry without any option
+    pub fn empty() -> Self{
+        Options{options: Vec::new()}
+    }
+
+    fn add(&mut self, name: &str, kind: OptionType, default: OptionValue) -> &mut Self{
+        self.options.push(UciOption{name: name.to_string(), kind, value: default.clone(), default});
+        self
+    }
+
+    /// Register a spin option, an integer between min and max
+    pub fn add_spin(&mut self, name: &str, default: i64, min: i64, max: i64) -> &mut Self{
+        self.add(name, OptionType::Spin{min, max}, OptionValue::Spin(default))
+    }
+
+    /// Register a check option, a boolean
+    pub fn add_check(&mut self, name: &str, default: bool) -> &mut Self{
+        self.add(name, OptionType::Check, OptionValue::Check(default))
+    }
+
+    /// Register a combo option, one of the predefined values
+    #[allow(dead_code)] // no combo option yet
+    pub fn add_combo(&mut self, name: &str, default: &str, values: &[&str]) -> &mut Self{
+        let values = values.iter().map(|var| var.to_string()).collect();
+        self.add(name, OptionType::Combo{values}, OptionValue::Combo(default.to_string()))
+    }
+
+    /// Register a string option
+    #[allow(dead_code)] // no string option yet
+    pub fn add_string(&mut self, name: &str, default: &str) -> &mut Self{
+        self.add(name, OptionType::String, OptionValue::String(default.to_string()))
+    }
+
+    /// Register a button option, an action without value
+    pub fn add_button(&mut self, name: &str) -> &mut Self{
+        self.add(name, OptionType::Button, OptionValue::Button)
+    }
+
+    /// Get an option, the name is case insensitive
+    pub fn get(&self, name: &str) -> Option<&UciOption>{
+        self.options.iter().find(|option| option.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Set an option from its text value and return the option
+    pub fn set(&mut self, name: &str, value: Option<&str>) -> Result<&UciOption, OptionError>{
+        let option = self.options.iter_mut()
+            .find(|option| option.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| OptionError::UnknownOption(name.to_string()))?;
+        option.value = option.parse_value(value)?;
+        Ok(option)
+    }
+
+    /// Get the value of a registered spin option
+    pub fn spin(&self, name: &str) -> i64{
+        match self.get(name).map(|option| &option.value) {
+            Some(OptionValue::Spin(value)) => *value,
+            _ => panic!("no spin option named {}", name),
+        }
+    }
+
+    /// Get the value of a registered check option
+    pub fn check(&self, name: &str) -> bool{
+        match self.get(name).map(|option| &option.value) {
+            Some(OptionValue::Check(value)) => *value,
+            _ => panic!("no check option named {}", name),
+        }
+    }
+
+    /// Iterate over the options in registration order
+    pub fn iter(&self) -> impl Iterator<Item=&UciOption>{
+        self.options.iter()
+    }
+}
+/// The engine options with their default values
+impl Default for Options{
+    fn default() -> Self {
+        let mut options = Options::empty();
+        options.add_spin(HASH, 16, 1, 1024)
+            .add_spin(THREADS, 1, 1, 1) // the search is single threaded
+            .add_spin(MULTI_PV, 1, 1, 1)
+            .add_button(CLEAR_HASH)
+            .add_check(PONDER, false)
+            .add_spin(MOVE_OVERHEAD, 30, 0, 5000);
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::uci::options::{Options, OptionError, OptionValue, HASH, PONDER, MOVE_OVERHEAD, CLEAR_HASH};
+
+    #[test]
+    fn test_advertise(){
+        let lines: Vec<String> = Options::default().iter().map(|option| option.to_string()).collect();
+        assert_eq!(lines, vec![
+            "option name Hash type spin default 16 min 1 max 1024",
+            "option name Threads type spin default 1 min 1 max 1",
+            "option name MultiPV type spin default 1 min 1 max 1",
+            "option name Clear Hash type button",
+            "option name Ponder type check default false",
+            "option name Move Overhead type spin default 30 min 0 max 5000",
+        ]);
+
+        let mut options = Options::empty();
+        options.add_combo("Style", "Normal", &["Solid", "Normal", "Risky"]).add_string("Book File", "");
+        let lines: Vec<String> = options.iter().map(|option| option.to_string()).collect();
+        assert_eq!(lines, vec![
+            "option name Style type combo default Normal var Solid var Normal var Risky",
+            "option name Book File type string default <empty>",
+        ]);
+    }
+
+    #[test]
+    fn test_set(){
+        let mut options = Options::default();
+        options.set("hash", Some("128")).unwrap();
+        assert_eq!(options.spin(HASH), 128);
+        options.set(PONDER, Some("true")).unwrap();
+        assert!(options.check(PONDER));
+        options.set(MOVE_OVERHEAD, Some("0")).unwrap();
+        assert_eq!(options.spin(MOVE_OVERHEAD), 0);
+        assert_eq!(options.set(CLEAR_HASH, None).unwrap().value, OptionValue::Button);
+
+        let mut options = Options::empty();
+        options.add_combo("Style", "Normal", &["Solid", "Normal", "Risky"]).add_string("Book File", "");
+        assert_eq!(options.set("Style", Some("risky")).unwrap().value, OptionValue::Combo("Risky".to_string()));
+        assert_eq!(options.set("Book File", Some("/tmp/book.bin")).unwrap().value, OptionValue::String("/tmp/book.bin".to_string()));
+        assert_eq!(options.set("Book File", Some("<empty>")).unwrap().value, OptionValue::String(String::new()));
+    }
+
+    #[test]
+    fn test_set_errors(){
+        let mut options = Options::default();
+        assert_eq!(options.set("Hashes", Some("1")).err(), Some(OptionError::UnknownOption("Hashes".to_string())));
+        assert_eq!(options.set(HASH, None).err(), Some(OptionError::MissingValue(HASH.to_string())));
+        assert_eq!(options.set(HASH, Some("0")).err(), Some(OptionError::InvalidValue(HASH.to_string(), "0".to_string())));
+        assert_eq!(options.set(HASH, Some("big")).err(), Some(OptionError::InvalidValue(HASH.to_string(), "big".to_string())));
+        assert_eq!(options.set(PONDER, Some("yes")).err(), Some(OptionError::InvalidValue(PONDER.to_string(), "yes".to_string())));
+        assert_eq!(options.spin(HASH), 16);
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt;
+use std::fmt::Formatter;
+use crate::engine::board::Board;
+use crate::engine::error::ChessError;
+use crate::engine::moves::Move;
+
+/// Error returned when a `position` command can not be understood
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PositionParseError{
+    /// Neither `startpos` nor `fen` was given
+    MissingPosition,
+    /// Unknown token instead of `startpos`, `fen` or `moves`
+    UnexpectedToken(String),
+    /// The fen could not be loaded, and why
+    InvalidFen(String, ChessError),
+    /// A move of the move list is not a valid uci move
+    InvalidMove(String),
+}
+impl fmt::Display for PositionParseError{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PositionParseError::MissingPosition => write!(f, "expected 'startpos' or 'fen'"),
+            PositionParseError::UnexpectedToken(token) => write!(f, "unexpected token '{}'", token),
+            PositionParseError::InvalidFen(fen, err) => write!(f, "invalid fen '{}': {}", fen, err),
+            PositionParseError::InvalidMove(mv) => write!(f, "invalid move '{}'", mv),
+        }
+    }
+}
+
+/// A parsed `position [startpos | fen <fen>] [moves <move>...]` command
+#[derive(Debug)]
+pub struct PositionCommand<'a>{
+    /// Position to start from
+    pub board: Board,
+    /// Moves to play from the start position, in uci notation
+    pub moves: Vec<&'a str>,
+}
+impl<'a> PositionCommand<'a>{
+    /// Parse the tokens following the `position` keyword
+    pub fn parse(details: &[&'a str]) -> Result<Self, PositionParseError>{
+        let (position, moves) = match details.iter().position(|&token| token == "moves") {
+            Some(index) => (&details[..index], &details[index + 1..]),
+            None => (details, &details[details.len()..]),
+        };
+
+        let board = match position.split_first() {
+            Some((&"startpos", [])) => Board::new_board(),
+            Some((&"startpos", [token, ..])) => return Err(PositionParseError::UnexpectedToken(token.to_string())),
+            Some((&"fen", fields)) => parse_fen(fields)?,
+            Some((token, _)) => return Err(PositionParseError::UnexpectedToken(token.to_string())),
+            None => return Err(PositionParseError::MissingPosition),
+        };
+
+        if let Some(mv) = moves.iter().find(|mv| mv.parse::<Move>().is_err()){
+            return Err(PositionParseError::InvalidMove(mv.to_string()))
+        }
+        Ok(PositionCommand{board, moves: moves.to_vec()})
+    }
+}
+
+/// Load a board from the fen fields of the command, the position must be valid.
+/// Some GUIs omit the halfmove and move counters, they default to "0 1"
+fn parse_fen(fields: &[&str]) -> Result<Board, PositionParseError>{
+    let mut fields = fields.to_vec();
+    if fields.len() == 4 {
+        fields.extend_from_slice(&["0", "1"]);
+    }
+    let fen = fields.join(" ");
+    match Board::try_from_fen(&fen).and_then(|board| board.validate().map(|_| board)) {
+        Ok(board) => Ok(board),
+        Err(err) => Err(PositionParseError::InvalidFen(fen, err)),
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::engine::board::Board;
+    use crate::engine::error::ChessError;
+    use crate::engine::piece::Color;
+    use crate::uci::position::{PositionCommand, PositionParseError};
+
+    fn parse(command: &str) -> Result<PositionCommand<'_>, PositionParseError>{
+        PositionCommand::parse(&command.split_whitespace().collect::<Vec<&str>>())
+    }
+
+    #[test]
+    fn test_parse_startpos(){
+        let command = parse("startpos").unwrap();
+        assert_eq!(command.board.to_fen(), Board::new_board().to_fen());
+        assert!(command.moves.is_empty());
+
+        let command = parse("startpos moves e2e4 e7e5 g1f3").unwrap();
+        assert_eq!(command.board.to_fen(), Board::new_board().to_fen());
+        assert_eq!(command.moves, vec!["e2e4", "e7e5", "g1f3"]);
+    }
+
+    #[test]
+    fn test_parse_fen(){
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let line = format!("fen {}", fen);
+        let command = parse(&line).unwrap();
+        assert_eq!(command.board.to_fen(), fen);
+        assert!(command.moves.is_empty());
+
+        let line = format!("fen {} moves e1g1 h3g2", fen);
+        let command = parse(&line).unwrap();
+        assert_eq!(command.board.to_fen(), fen);
+        assert_eq!(command.moves, vec!["e1g1", "h3g2"]);
+
+        // missing counters
+        let command = parse("fen r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - moves e1g1").unwrap();
+        assert_eq!(command.board.to_fen(), fen);
+        assert_eq!(command.moves, vec!["e1g1"]);
+    }
+
+    #[test]
+    fn test_parse_errors(){
+        assert_eq!(parse("").err(), Some(PositionParseError::MissingPosition));
+        assert_eq!(parse("moves e2e4").err(), Some(PositionParseError::MissingPosition));
+        assert_eq!(parse("startfen").err(), Some(PositionParseError::UnexpectedToken("startfen".to_string())));
+        assert_eq!(parse("startpos e2e4").err(), Some(PositionParseError::UnexpectedToken("e2e4".to_string())));
+        assert_eq!(parse("startpos moves e2e4 e7").err(), Some(PositionParseError::InvalidMove("e7".to_string())));
+        assert!(parse("fen rnbqkbnrr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_err());
+        assert!(parse("fen rnbqkbnr/pppppppp/8/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_err());
+        assert!(parse("fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq").is_err());
+        assert_eq!(parse("fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1").err(),
+                   Some(PositionParseError::InvalidFen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1".to_string(),
+                                                       ChessError::FenField{field: 1, value: "x".to_string()})));
+        assert_eq!(parse("fen 8/8/8/8/8/8/8/8 w - - 0 1").err(),
+                   Some(PositionParseError::InvalidFen("8/8/8/8/8/8/8/8 w - - 0 1".to_string(),
+                                                       ChessError::KingCount{color: Color::White, count: 0})));
+    }
+}
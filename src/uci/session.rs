@@ -0,0 +1,232 @@
+use crate::engine::board::Board;
+use crate::engine::moves::Move;
+use crate::engine::search::{Search, SearchResult, SearchLimits, SearchSignals};
+use crate::engine::time_manager::TimeManager;
+use crate::uci::info::format_info;
+use crate::uci::options::{Options, OptionError, MOVE_OVERHEAD, CLEAR_HASH, HASH, PONDER};
+use std::time::Duration;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::thread::JoinHandle;
+
+/// Depth used when `go` is sent without any limit
+const DEFAULT_DEPTH: u32 = 4;
+
+/// State of the engine kept between uci commands
+pub struct Session{
+    /// Position the current game started from
+    pub start: Board,
+    /// Current position
+    pub board: Board,
+    /// Moves played since the start position
+    pub history: Vec<Move>,
+    /// Engine settings
+    pub options: Options,
+    /// Search state, kept for the whole game. None while a search is running
+    search: Option<Search>,
+    /// Flags controlling the running search
+    signals: Arc<SearchSignals>,
+    /// Thread running the current search, it gives the search state back when done
+    worker: Option<JoinHandle<(Search, SearchResult)>>,
+}
+impl Session{
+    /// Create a new session on the starting position
+    pub fn new() -> Self{
+        let options = Options::default();
+        let search = Session::new_search(options.spin(HASH) as usize);
+        Session{
+            start: Board::new_board(),
+            board: Board::new_board(),
+            history: Vec::new(),
+            options,
+            signals: search.signals(),
+            search: Some(search),
+            worker: None,
+        }
+    }
+
+    /// Reset the session for a new game, options are kept
+    pub fn new_game(&mut self){
+        self.stop();
+        self.set_position(Board::new_board());
+        self.search_mut().clear_hash();
+    }
+
+    /// Get the search state, the search must not be running
+    fn search_mut(&mut self) -> &mut Search{
+        self.search.as_mut().expect("search is running")
+    }
+
+    /// Create a search state reporting its progress to the GUI
+    fn new_search(hash_size: usize) -> Search{
+        let mut search = Search::with_hash_size(hash_size);
+        search.set_info_callback(Box::new(|info| println!("{}", format_info(&info))));
+        search
+    }
+
+    /// Set a new position, clearing the move history
+    pub fn set_position(&mut self, board: Board){
+        self.start = board;
+        self.board = board;
+        self.history.clear();
+    }
+
+    /// Play a move on the current position
+    pub fn play_move(&mut self, mv: Move){
+        self.board = self.board.apply_move(&mv);
+        self.history.push(mv);
+    }
+
+    /// Set an option and apply it
+    pub fn set_option(&mut self, name: &str, value: Option<&str>) -> Result<(), OptionError>{
+        let option = self.options.set(name, value)?;
+        let name = option.name.clone();
+        if name == CLEAR_HASH {
+            self.stop();
+            self.search_mut().clear_hash();
+        } else if name == HASH {
+            self.stop();
+            let size = self.options.spin(HASH) as usize;
+            self.search_mut().resize_hash(size);
+        }
+        Ok(())
+    }
+
+    /// Start searching the current position within the limits on a worker thread.
+    /// The best move is sent to the GUI when the search is over.
+    pub fn go(&mut self, mut limits: SearchLimits){
+        self.stop();
+        // searchmoves only restricts the moves, it does not limit the search
+        let unlimited = SearchLimits{searchmoves: Vec::new(), ..limits.clone()} == SearchLimits::default();
+        if unlimited{
+            limits.depth = Some(DEFAULT_DEPTH);
+        }
+        let mut search = self.search.take().expect("search state is lost");
+        let move_overhead = Duration::from_millis(self.options.spin(MOVE_OVERHEAD) as u64);
+        let time = TimeManager::new(&limits, self.board.side, move_overhead);
+        let board = self.board;
+        // the move to ponder on is only sent to a GUI that may ponder
+        let send_ponder = self.options.check(PONDER);
+        let signals = self.signals.clone();
+        signals.stop.store(false, Ordering::SeqCst);
+        signals.ponder.store(limits.ponder, Ordering::SeqCst);
+
+        self.worker = Some(thread::spawn(move || {
+            let result = search.search(&board, &limits, time);
+            // the best move can not be sent before stop in infinite mode, or before ponderhit
+            while !signals.stop.load(Ordering::SeqCst)
+                && (limits.infinite || signals.ponder.load(Ordering::SeqCst)){
+                thread::park();
+            }
+            match (result.best_move, result.pv.get(1).filter(|_| send_ponder)) {
+                (Some(mv), Some(ponder)) => println!("bestmove {} ponder {}", mv.to_uci(), ponder.to_uci()),
+                (Some(mv), None) => println!("bestmove {}", mv.to_uci()),
+                (None, _) => println!("bestmove 0000"), // no legal move
+            }
+            (search, result)
+        }));
+    }
+
+    /// Wait for the running search to end, if any, and return its result
+    pub fn wait(&mut self) -> Option<SearchResult>{
+        let worker = self.worker.take()?;
+        let (search, result) = worker.join().expect("search thread panicked");
+        self.search = Some(search);
+        Some(result)
+    }
+
+    /// Stop the running search, if any, and return its result
+    pub fn stop(&mut self) -> Option<SearchResult>{
+        let worker = self.worker.as_ref()?;
+        self.signals.stop.store(true, Ordering::SeqCst);
+        worker.thread().unpark();
+        self.wait()
+    }
+
+    /// The opponent played the pondered move, the search goes on with its time limits
+    pub fn ponderhit(&mut self){
+        self.signals.ponder.store(false, Ordering::SeqCst);
+        if let Some(worker) = &self.worker{
+            worker.thread().unpark();
+        }
+    }
+}
+impl Default for Session{
+    fn default() -> Self {
+        Session::new()
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::engine::board::Board;
+    use crate::engine::moves::Move;
+    use crate::engine::search::SearchLimits;
+    use crate::uci::session::Session;
+    use crate::uci::options::{MOVE_OVERHEAD, CLEAR_HASH, HASH};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_play_moves(){
+        let mut session = Session::new();
+        for mv in ["e2e4", "c7c5", "g1f3"].iter(){
+            session.play_move(Move::new_on_board(mv, &session.board));
+        }
+        assert_eq!(session.board.to_fen(), "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+        assert_eq!(session.history.len(), 3);
+        assert_eq!(session.start.to_fen(), Board::new_board().to_fen());
+
+        session.new_game();
+        assert_eq!(session.board.to_fen(), Board::new_board().to_fen());
+        assert!(session.history.is_empty());
+    }
+
+    #[test]
+    fn test_search_current_position(){
+        let mut session = Session::new();
+        session.set_position(Board::new_from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1"));
+        session.go(SearchLimits::default());
+        assert_eq!(session.wait().unwrap().best_move.unwrap().to_uci(), "a1a8");
+        assert!(session.wait().is_none());
+    }
+
+    #[test]
+    fn test_set_option(){
+        let mut session = Session::new();
+        session.set_option("move overhead", Some("100")).unwrap();
+        assert_eq!(session.options.spin(MOVE_OVERHEAD), 100);
+        session.set_option(CLEAR_HASH, None).unwrap();
+        session.set_option("Hash", Some("1")).unwrap();
+        assert_eq!(session.options.spin(HASH), 1);
+        assert!(session.set_option("Move Overhead", Some("-1")).is_err());
+        assert_eq!(session.options.spin(MOVE_OVERHEAD), 100);
+    }
+
+    #[test]
+    fn test_stop(){
+        let mut session = Session::new();
+        session.go(SearchLimits{infinite: true, ..SearchLimits::default()});
+        thread::sleep(Duration::from_millis(50));
+        assert!(session.stop().unwrap().best_move.is_some());
+        assert!(session.stop().is_none());
+
+        // an infinite search keeps the best move until stop, even when the search is over
+        session.set_position(Board::new_from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1"));
+        session.go(SearchLimits{infinite: true, mate: Some(1), ..SearchLimits::default()});
+        thread::sleep(Duration::from_millis(50));
+        assert!(!session.worker.as_ref().unwrap().is_finished());
+        assert_eq!(session.stop().unwrap().best_move.unwrap().to_uci(), "a1a8");
+    }
+
+    #[test]
+    fn test_ponderhit(){
+        let mut session = Session::new();
+        session.go(SearchLimits{ponder: true, depth: Some(1), ..SearchLimits::default()});
+        thread::sleep(Duration::from_millis(50));
+        assert!(!session.worker.as_ref().unwrap().is_finished());
+        session.ponderhit();
+        assert!(session.wait().unwrap().best_move.is_some());
+    }
+}
\ No newline at end of file